@@ -1,60 +1,248 @@
-use regex::Regex;
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+};
 
-use crate::Solution;
+use crate::{Options, Solution};
 
 pub struct Day1;
 
-impl Day1 {
-    fn parse(input: &str) -> usize {
-        match input {
-            "0" | "zero" => 0,
-            "1" | "one" => 1,
-            "2" | "two" => 2,
-            "3" | "three" => 3,
-            "4" | "four" => 4,
-            "5" | "five" => 5,
-            "6" | "six" => 6,
-            "7" | "seven" => 7,
-            "8" | "eight" => 8,
-            "9" | "nine" => 9,
-            x => panic!("{x} is not a digit"),
+const DIGITS: [(&str, usize); 10] = [
+    ("0", 0),
+    ("1", 1),
+    ("2", 2),
+    ("3", 3),
+    ("4", 4),
+    ("5", 5),
+    ("6", 6),
+    ("7", 7),
+    ("8", 8),
+    ("9", 9),
+];
+
+const ENGLISH: [(&str, usize); 10] = [
+    ("zero", 0),
+    ("one", 1),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("eight", 8),
+    ("nine", 9),
+];
+
+/// Aho–Corasick automaton over a vocabulary of digit spellings. It finds every
+/// occurrence of every word in one pass over a line, including the ones that
+/// overlap, like `eightwo`.
+#[derive(Debug)]
+struct Matcher {
+    /// the trie of all the words, `next[state][c]` is the state after reading `c`
+    next: Vec<HashMap<char, usize>>,
+    /// the state for the longest proper suffix of this state that is also in the trie
+    fail: Vec<usize>,
+    /// every word that ends at this state, as `(length, value)`
+    words: Vec<Vec<(usize, usize)>>,
+}
+
+impl Matcher {
+    fn new<'a>(vocabulary: impl IntoIterator<Item = (&'a str, usize)>) -> Self {
+        let mut matcher = Matcher {
+            next: vec![HashMap::new()],
+            fail: vec![0],
+            words: vec![vec![]],
+        };
+        for (word, value) in vocabulary {
+            let mut state = 0;
+            for c in word.chars() {
+                state = match matcher.next[state].get(&c) {
+                    Some(next) => *next,
+                    None => {
+                        matcher.next.push(HashMap::new());
+                        matcher.fail.push(0);
+                        matcher.words.push(vec![]);
+                        let next = matcher.next.len() - 1;
+                        matcher.next[state].insert(c, next);
+                        next
+                    }
+                };
+            }
+            matcher.words[state].push((word.chars().count(), value));
+        }
+
+        // breadth first, so that every shorter suffix already has its failure link
+        let mut queue: VecDeque<usize> = matcher.next[0].values().copied().collect();
+        while let Some(state) = queue.pop_front() {
+            let children = matcher.next[state]
+                .iter()
+                .map(|(c, child)| (*c, *child))
+                .collect::<Vec<_>>();
+            for (c, child) in children {
+                let fail = matcher.step(matcher.fail[state], c);
+                matcher.fail[child] = if fail == child { 0 } else { fail };
+                let inherited = matcher.words[matcher.fail[child]].clone();
+                matcher.words[child].extend(inherited);
+                queue.push_back(child);
+            }
         }
+        matcher
     }
 
-    /// Return the list of matches starting at every index of the string
-    fn overlapping_search<'a>(regex: &'a Regex, input: &'a str) -> Vec<&'a str> {
-        (0..input.len())
-            .into_iter()
-            .filter_map(|i| regex.find(&input[i..]).map(|m| m.as_str()))
-            .collect()
+    fn step(&self, mut state: usize, c: char) -> usize {
+        loop {
+            if let Some(next) = self.next[state].get(&c) {
+                return *next;
+            }
+            if state == 0 {
+                return 0;
+            }
+            state = self.fail[state];
+        }
+    }
+
+    /// The values of the first and last words in `line`, by where they start.
+    /// If two words start at the same place, the longer one wins.
+    fn first_and_last(&self, line: &str) -> Option<(usize, usize)> {
+        let mut state = 0;
+        let mut first: Option<(usize, usize, usize)> = None;
+        let mut last: Option<(usize, usize, usize)> = None;
+        for (end, c) in line.chars().enumerate() {
+            state = self.step(state, c);
+            for &(len, value) in &self.words[state] {
+                let start = end + 1 - len;
+                if first.is_none_or(|(s, l, _)| (start, usize::MAX - len) < (s, usize::MAX - l)) {
+                    first = Some((start, len, value));
+                }
+                if last.is_none_or(|(s, l, _)| (start, len) > (s, l)) {
+                    last = Some((start, len, value));
+                }
+            }
+        }
+        Some((first?.2, last?.2))
     }
 }
 
-impl Solution for Day1 {
-    fn part1(input: &str) -> Option<i64> {
+impl Day1 {
+    /// Add up the calibration values of every line, reporting the lines that don't
+    /// have any digits in them
+    fn calibrate(input: &str, matcher: &Matcher) -> Option<i64> {
         Some(
             input
                 .lines()
-                .map(|line| line.matches(char::is_numeric).collect::<Vec<_>>())
-                .map(|digits| (digits[0], digits[digits.len() - 1]))
-                .map(|(first, last)| format!("{first}{last}").parse::<usize>().unwrap())
+                .enumerate()
+                .filter_map(|(i, line)| {
+                    let digits = matcher.first_and_last(line);
+                    if digits.is_none() {
+                        eprintln!("Line {} has no digits: {line:?}", i + 1);
+                    }
+                    digits
+                })
+                .map(|(first, last)| first * 10 + last)
                 .sum::<usize>() as i64,
         )
     }
 
+    /// Read a vocabulary with one `word value` pair on each line
+    fn load_vocabulary(path: &str) -> Vec<(String, usize)> {
+        fs::read_to_string(path)
+            .expect("Unable to read vocabulary")
+            .lines()
+            .filter_map(|line| line.split_once(char::is_whitespace))
+            .map(|(word, value)| {
+                let value = value.trim().parse().expect("Invalid digit in vocabulary");
+                (word.to_string(), value)
+            })
+            .collect()
+    }
+}
+
+impl Solution for Day1 {
+    fn part1(input: &str) -> Option<i64> {
+        Self::calibrate(input, &Matcher::new(DIGITS))
+    }
+
     fn part2(input: &str) -> Option<i64> {
-        let re = Regex::new("(one|two|three|four|five|six|seven|eight|nine|zero|[0-9])").unwrap();
-        Some(
-            input
-                // for each line
-                .lines()
-                // gather all regex matches for that line
-                .map(|line| Self::overlapping_search(&re, line))
-                // pull out the first and last one
-                .map(|digits| (digits[0], digits[digits.len() - 1]))
-                .map(|(first, last)| (Self::parse(first), Self::parse(last)))
-                .map(|(first, last)| first * 10 + last)
-                .sum::<usize>() as i64,
-        )
+        Self::part2_with(input, &Options::default())
+    }
+
+    /// Pass `-o words=FILE` to spell out the digits in another language, with a line
+    /// like `un 1` for each word
+    fn part2_with(input: &str, opts: &Options) -> Option<i64> {
+        let words = match opts.get::<String>("words") {
+            Some(path) => Self::load_vocabulary(&path),
+            None => ENGLISH
+                .iter()
+                .map(|(word, value)| (word.to_string(), *value))
+                .collect(),
+        };
+        let matcher = Matcher::new(
+            DIGITS
+                .into_iter()
+                .chain(words.iter().map(|(word, value)| (word.as_str(), *value))),
+        );
+        Self::calibrate(input, &matcher)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn english() -> Matcher {
+        Matcher::new(DIGITS.into_iter().chain(ENGLISH))
+    }
+
+    #[test]
+    fn example() {
+        let input = "two1nine\neightwothree\nabcone2threexyz\nxtwone3four\n\
+                     4nineeightseven2\nzoneight234\n7pqrstsixteen";
+        assert_eq!(
+            Day1::part1("1abc2\npqr3stu8vwx\na1b2c3d4e5f\ntreb7uchet"),
+            Some(142)
+        );
+        assert_eq!(Day1::part2(input), Some(281));
+    }
+
+    #[test]
+    fn overlapping_words() {
+        let matcher = english();
+        assert_eq!(matcher.first_and_last("eightwo"), Some((8, 2)));
+        assert_eq!(matcher.first_and_last("oneight"), Some((1, 8)));
+        assert_eq!(matcher.first_and_last("xtwoneight"), Some((2, 8)));
+    }
+
+    #[test]
+    fn word_that_ends_another() {
+        // `even` only shows up through the failure link out of `seven`
+        let matcher = Matcher::new([("seven", 7), ("even", 2)]);
+        assert_eq!(matcher.first_and_last("xseven"), Some((7, 2)));
+        assert_eq!(matcher.first_and_last("evenseven"), Some((2, 2)));
+        assert_eq!(matcher.first_and_last("sevens"), Some((7, 2)));
+    }
+
+    #[test]
+    fn longer_word_wins_at_same_start() {
+        let matcher = Matcher::new([("on", 5), ("one", 1)]);
+        assert_eq!(matcher.first_and_last("one"), Some((1, 1)));
+        assert_eq!(matcher.first_and_last("onx"), Some((5, 5)));
+    }
+
+    #[test]
+    fn line_without_digits() {
+        assert_eq!(english().first_and_last("abcdef"), None);
+        assert_eq!(english().first_and_last(""), None);
+        // the line is reported and skipped, the others still count
+        assert_eq!(Day1::part1("abc\n1x2"), Some(12));
+        assert_eq!(Day1::part2("twone\nnothing"), Some(21));
+    }
+
+    #[test]
+    fn other_vocabulary() {
+        let french = [("un", 1), ("deux", 2), ("trois", 3), ("huit", 8)];
+        let matcher = Matcher::new(DIGITS.into_iter().chain(french));
+        assert_eq!(matcher.first_and_last("undeuxtrois"), Some((1, 3)));
+        assert_eq!(matcher.first_and_last("xhuit7deuxz"), Some((8, 2)));
+        assert_eq!(matcher.first_and_last("eight"), None);
     }
 }
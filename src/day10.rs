@@ -1,43 +1,47 @@
 use std::{
-    cmp,
-    fmt::Display,
-    ops::{Add, Neg},
+    cell::OnceCell,
+    collections::HashSet,
+    fmt::{Display, Write},
     str::FromStr,
 };
 
 use color_print::cprint;
-use itertools::{FoldWhile, Itertools};
+use itertools::Itertools;
 
-use crate::Solution;
+use crate::{
+    grid::{Direction, FromChar, Grid, Pos},
+    search::bfs,
+    Options, Solution,
+};
 
 pub struct Day10;
 
 #[derive(Debug)]
 struct InputGrid {
-    grid: Vec<Vec<Pipe>>,
-    width: usize,
-    height: usize,
+    grid: Grid<Pipe>,
+    /// the pipe under the start, worked out the first time it's needed because
+    /// that means following the whole loop
+    start_pipe: OnceCell<Option<Pipe>>,
 }
 
 impl FromStr for InputGrid {
     type Err = &'static str;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let grid = s
-            .lines()
-            .map(|line| line.chars().map(Pipe::from).collect_vec())
-            .collect_vec();
-        let width = grid[0].len() - 1;
-        let height = grid.len() - 1;
         Ok(InputGrid {
-            grid,
-            width,
-            height,
+            grid: s.parse()?,
+            start_pipe: OnceCell::new(),
         })
     }
 }
 
 #[derive(Debug, PartialEq, Eq)]
+enum LoopError {
+    NoStart,
+    NoLoop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Pipe {
     Vertical,
     Horizontal,
@@ -49,59 +53,18 @@ enum Pipe {
     Start,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum Direction {
-    North,
-    East,
-    South,
-    West,
-    Nope,
-}
-
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-struct Pos(usize, usize);
-
-impl Add<&Direction> for Pos {
-    type Output = Pos;
-
-    fn add(self, rhs: &Direction) -> Self::Output {
-        let Pos(x, y) = self;
-        match rhs {
-            Direction::North => Pos(x, y - 1),
-            Direction::East => Pos(x + 1, y),
-            Direction::South => Pos(x, y + 1),
-            Direction::West => Pos(x - 1, y),
-            Direction::Nope => Pos(x, y),
-        }
-    }
-}
-
-impl Neg for Direction {
-    type Output = Direction;
-
-    fn neg(self) -> Self::Output {
-        match self {
-            Direction::North => Direction::South,
-            Direction::East => Direction::West,
-            Direction::South => Direction::North,
-            Direction::West => Direction::East,
-            Direction::Nope => Direction::Nope,
-        }
-    }
-}
-
-impl From<char> for Pipe {
-    fn from(value: char) -> Self {
+impl FromChar for Pipe {
+    fn from_char(value: char) -> Result<Self, &'static str> {
         match value {
-            '|' => Pipe::Vertical,
-            '-' => Pipe::Horizontal,
-            'L' => Pipe::NorthEastBend,
-            'J' => Pipe::NorthWestBend,
-            '7' => Pipe::SouthWestBend,
-            'F' => Pipe::SouthEastBend,
-            '.' => Pipe::Ground,
-            'S' => Pipe::Start,
-            _ => panic!("Unknown char"),
+            '|' => Ok(Pipe::Vertical),
+            '-' => Ok(Pipe::Horizontal),
+            'L' => Ok(Pipe::NorthEastBend),
+            'J' => Ok(Pipe::NorthWestBend),
+            '7' => Ok(Pipe::SouthWestBend),
+            'F' => Ok(Pipe::SouthEastBend),
+            '.' => Ok(Pipe::Ground),
+            'S' => Ok(Pipe::Start),
+            _ => Err("Unknown char"),
         }
     }
 }
@@ -121,181 +84,165 @@ impl Display for Pipe {
     }
 }
 
-impl Pipe {
-    fn connection_directions(&self) -> (Direction, Direction) {
+impl Display for LoopError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Pipe::Vertical => (Direction::North, Direction::South),
-            Pipe::Horizontal => (Direction::East, Direction::West),
-            Pipe::NorthEastBend => (Direction::North, Direction::East),
-            Pipe::NorthWestBend => (Direction::North, Direction::West),
-            Pipe::SouthWestBend => (Direction::South, Direction::West),
-            Pipe::SouthEastBend => (Direction::South, Direction::East),
-            Pipe::Ground => (Direction::Nope, Direction::Nope),
-            Pipe::Start => (Direction::Nope, Direction::Nope),
+            LoopError::NoStart => write!(f, "There is no start tile"),
+            LoopError::NoLoop => write!(f, "No loop of pipes goes through the start"),
         }
     }
 }
 
-impl InputGrid {
-    fn start_position(&self) -> Pos {
-        for x in 0..=self.width {
-            for y in 0..=self.height {
-                if self.grid[y][x] == Pipe::Start {
-                    return Pos(x, y);
-                }
-            }
+impl Pipe {
+    fn connection_directions(&self) -> Option<(Direction, Direction)> {
+        match self {
+            Pipe::Vertical => Some((Direction::North, Direction::South)),
+            Pipe::Horizontal => Some((Direction::East, Direction::West)),
+            Pipe::NorthEastBend => Some((Direction::North, Direction::East)),
+            Pipe::NorthWestBend => Some((Direction::North, Direction::West)),
+            Pipe::SouthWestBend => Some((Direction::South, Direction::West)),
+            Pipe::SouthEastBend => Some((Direction::South, Direction::East)),
+            Pipe::Ground => None,
+            Pipe::Start => None,
         }
-        panic!("Didn't find any start position");
     }
 
-    /// Given the position of a pipe, search neighbors for connecting pipes.
-    /// If there is one that exists, return that pipe.
-    /// There is almost certainly a smarter way of doing this. But I've already commited lol.
-    fn next_position(&self, Pos(x, y): Pos, from: Direction) -> Option<Direction> {
-        match (&self.grid[y][x], from) {
-            (Pipe::Vertical, Direction::North)
-                if self.is_connected_to(Pos(x, y), Direction::South) =>
-            {
-                Some(Direction::South)
-            }
-            (Pipe::Vertical, Direction::South)
-                if self.is_connected_to(Pos(x, y), Direction::North) =>
-            {
-                Some(Direction::North)
-            }
-            (Pipe::Vertical, _) => None,
-
-            (Pipe::Horizontal, Direction::West)
-                if self.is_connected_to(Pos(x, y), Direction::East) =>
-            {
-                Some(Direction::East)
-            }
-            (Pipe::Horizontal, Direction::East)
-                if self.is_connected_to(Pos(x, y), Direction::West) =>
-            {
-                Some(Direction::West)
-            }
-            (Pipe::Horizontal, _) => None,
+    /// The pipe that connects these two directions, the other way around from
+    /// `connection_directions`
+    fn connecting(d0: Direction, d1: Direction) -> Option<Pipe> {
+        [
+            Pipe::Vertical,
+            Pipe::Horizontal,
+            Pipe::NorthEastBend,
+            Pipe::NorthWestBend,
+            Pipe::SouthWestBend,
+            Pipe::SouthEastBend,
+        ]
+        .into_iter()
+        .find(|pipe| {
+            pipe.connection_directions()
+                .is_some_and(|dirs| dirs == (d0, d1) || dirs == (d1, d0))
+        })
+    }
 
-            (Pipe::NorthEastBend, Direction::North)
-                if self.is_connected_to(Pos(x, y), Direction::East) =>
-            {
-                Some(Direction::East)
-            }
-            (Pipe::NorthEastBend, Direction::East)
-                if self.is_connected_to(Pos(x, y), Direction::North) =>
-            {
-                Some(Direction::North)
-            }
-            (Pipe::NorthEastBend, _) => None,
+    /// The pipe drawn with heavy lines, to pick out the loop
+    fn heavy(&self) -> char {
+        match self {
+            Pipe::Vertical => '┃',
+            Pipe::Horizontal => '━',
+            Pipe::NorthEastBend => '┗',
+            Pipe::NorthWestBend => '┛',
+            Pipe::SouthWestBend => '┓',
+            Pipe::SouthEastBend => '┏',
+            Pipe::Ground => '•',
+            Pipe::Start => 'S',
+        }
+    }
 
-            (Pipe::NorthWestBend, Direction::West)
-                if self.is_connected_to(Pos(x, y), Direction::North) =>
-            {
-                Some(Direction::North)
-            }
-            (Pipe::NorthWestBend, Direction::North)
-                if self.is_connected_to(Pos(x, y), Direction::West) =>
-            {
-                Some(Direction::West)
-            }
-            (Pipe::NorthWestBend, _) => None,
+    fn is_bend(&self) -> bool {
+        matches!(
+            self,
+            Pipe::NorthEastBend | Pipe::NorthWestBend | Pipe::SouthWestBend | Pipe::SouthEastBend
+        )
+    }
+}
 
-            (Pipe::SouthWestBend, Direction::South)
-                if self.is_connected_to(Pos(x, y), Direction::West) =>
-            {
-                Some(Direction::West)
-            }
-            (Pipe::SouthWestBend, Direction::West)
-                if self.is_connected_to(Pos(x, y), Direction::South) =>
-            {
-                Some(Direction::South)
-            }
-            (Pipe::SouthWestBend, _) => None,
+impl InputGrid {
+    fn start_position(&self) -> Pos {
+        self.grid
+            .position(|pipe| pipe == &Pipe::Start)
+            .expect("Didn't find any start position")
+    }
 
-            (Pipe::SouthEastBend, Direction::South)
-                if self.is_connected_to(Pos(x, y), Direction::East) =>
-            {
-                Some(Direction::East)
-            }
-            (Pipe::SouthEastBend, Direction::East)
-                if self.is_connected_to(Pos(x, y), Direction::South) =>
-            {
-                Some(Direction::South)
-            }
-            (Pipe::SouthEastBend, _) => None,
+    /// The pipe hidden under the start, worked out from the neighbors that the loop
+    /// leaves and comes back through. Other neighbors might point at the start too,
+    /// so we can't go by them alone.
+    fn start_pipe(&self) -> Option<Pipe> {
+        *self.start_pipe.get_or_init(|| {
+            let start = self.start_position();
+            let tiles = self.find_loop().ok()?;
+            Pipe::connecting(
+                self.direction_to(start, tiles[0])?,
+                self.direction_to(start, tiles[tiles.len() - 2])?,
+            )
+        })
+    }
 
-            (Pipe::Ground, _) => None,
+    /// The direction to go from `from` to its neighbor `to`
+    fn direction_to(&self, from: Pos, to: Pos) -> Option<Direction> {
+        Direction::ALL
+            .into_iter()
+            .find(|dir| self.grid.step(from, *dir) == Some(to))
+    }
 
-            (Pipe::Start, _) if self.is_connected_to(Pos(x, y), Direction::North) => {
-                Some(Direction::North)
-            }
-            (Pipe::Start, _) if self.is_connected_to(Pos(x, y), Direction::East) => {
-                Some(Direction::East)
-            }
-            (Pipe::Start, _) if self.is_connected_to(Pos(x, y), Direction::South) => {
-                Some(Direction::South)
-            }
-            (Pipe::Start, _) if self.is_connected_to(Pos(x, y), Direction::West) => {
-                Some(Direction::West)
-            }
-            (Pipe::Start, _) => None,
+    /// The pipe at `pos`, seeing through the start to the pipe under it
+    fn pipe_at(&self, pos: Pos) -> Pipe {
+        match self.grid[pos] {
+            Pipe::Start => self.start_pipe().unwrap_or(Pipe::Start),
+            pipe => pipe,
         }
     }
 
-    fn is_connected_to(&self, Pos(x, y): Pos, dir: Direction) -> bool {
-        match (dir, x, y) {
-            // there is nothing north of the top
-            (Direction::North, _, 0) => false,
-            // nothing east of the right hand side
-            (Direction::East, x, _) if x == self.width => false,
-            // nothing south of the bottom
-            (Direction::South, _, y) if y == self.height => false,
-            // nothing west of the left hand side
-            (Direction::West, 0, _) => false,
-
-            (Direction::North, _, _) => {
-                let (d0, d1) = self.grid[y - 1][x].connection_directions();
-                d0 == Direction::South || d1 == Direction::South
-            }
-            (Direction::East, _, _) => {
-                let (d0, d1) = self.grid[y][x + 1].connection_directions();
-                d0 == Direction::West || d1 == Direction::West
-            }
-            (Direction::South, _, _) => {
-                let (d0, d1) = self.grid[y + 1][x].connection_directions();
-                d0 == Direction::North || d1 == Direction::North
-            }
-            (Direction::West, _, _) => {
-                let (d0, d1) = self.grid[y][x - 1].connection_directions();
-                d0 == Direction::East || d1 == Direction::East
-            }
-            (Direction::Nope, _, _) => false,
-        }
+    /// Count the tiles inside the loop without looking at them at all.
+    ///
+    /// The shoelace formula gives the area of the polygon through the centers of the
+    /// loop's corners. Pick's theorem relates that area to the number of whole tiles
+    /// on the boundary `b` and inside it `i`: `A = i + b / 2 - 1`. Every loop tile is
+    /// on the boundary, so `i = A - b / 2 + 1`.
+    fn inside_by_area(&self, tiles: &[Pos]) -> i64 {
+        let corners = tiles
+            .iter()
+            .filter(|pos| self.pipe_at(**pos).is_bend())
+            .map(|Pos(x, y)| (*x as i64, *y as i64))
+            .collect_vec();
+        let twice_area = corners
+            .iter()
+            .circular_tuple_windows()
+            .map(|((x0, y0), (x1, y1))| x0 * y1 - x1 * y0)
+            .sum::<i64>()
+            .abs();
+        let boundary = tiles.len() as i64;
+        (twice_area - boundary) / 2 + 1
     }
 
-    fn find_path(&self) -> Vec<Pos> {
-        let (_, _, path) = (0..)
-            .fold_while(
-                (self.start_position(), Direction::Nope, vec![]),
-                |(pos, dir, mut hist), _| {
-                    if let Some(next_dir) = self.next_position(pos, -dir) {
-                        hist.push(pos + &next_dir);
-                        FoldWhile::Continue((pos + &next_dir, next_dir, hist))
-                    } else {
-                        FoldWhile::Done((pos, dir, hist))
-                    }
-                },
-            )
-            .into_inner();
-        path
+    /// Find the loop through the start, by trying every direction that leaves it in
+    /// turn. Each pipe only has one way forward, so junk pipes that point at the loop
+    /// never get followed, and a dead end means that direction wasn't part of the loop.
+    /// The loop comes back as its tiles in order, ending with the start.
+    fn find_loop(&self) -> Result<Vec<Pos>, LoopError> {
+        let start = self
+            .grid
+            .position(|pipe| pipe == &Pipe::Start)
+            .ok_or(LoopError::NoStart)?;
+        Direction::ALL
+            .into_iter()
+            .find_map(|dir| self.follow(start, dir))
+            .ok_or(LoopError::NoLoop)
+    }
+
+    /// Follow the pipes from the start going in `dir`, if they lead back to it
+    fn follow(&self, start: Pos, mut dir: Direction) -> Option<Vec<Pos>> {
+        let mut pos = self.grid.step(start, dir)?;
+        let mut tiles = vec![pos];
+        while pos != start {
+            // the pipe has to connect back the way we came in
+            let (d0, d1) = self.grid[pos].connection_directions()?;
+            dir = match -dir {
+                from if from == d0 => d1,
+                from if from == d1 => d0,
+                _ => return None,
+            };
+            pos = self.grid.step(pos, dir)?;
+            tiles.push(pos);
+        }
+        Some(tiles)
     }
 
     fn winding_number(&self, path: &[Pos], dir: Direction) -> i64 {
         let (_, winding) = path
             .iter()
-            .map(|Pos(x, y)| &self.grid[*y][*x])
-            .filter(|&pipe| pipe != &Pipe::Vertical && pipe != &Pipe::Horizontal)
+            .map(|pos| self.pipe_at(*pos))
+            .filter(|pipe| *pipe != Pipe::Vertical && *pipe != Pipe::Horizontal)
             .fold((dir, 0), |(dir, winding), pipe| match (pipe, -dir) {
                 (Pipe::Vertical, _)
                 | (Pipe::Horizontal, _)
@@ -325,14 +272,15 @@ impl InputGrid {
         }
     }
 
-    fn display_with_path(&self, path: &[Pos], outside: &[Pos]) {
-        for (y, row) in self.grid.iter().enumerate() {
+    /// Print the maze with the start in red, the loop in green and the inside in blue
+    fn display(&self, tiles: &HashSet<Pos>, inside: &HashSet<Pos>) {
+        for (y, row) in self.grid.rows().enumerate() {
             for (x, el) in row.iter().enumerate() {
-                if Pos(x, y) == path[0] {
+                if *el == Pipe::Start {
                     cprint!("<red>{el}</>");
-                } else if path.contains(&Pos(x, y)) {
+                } else if tiles.contains(&Pos(x, y)) {
                     cprint!("<green>{el}</>");
-                } else if outside.contains(&Pos(x, y)) {
+                } else if inside.contains(&Pos(x, y)) {
                     cprint!("<blue>{el}</>");
                 } else {
                     print!("{el}");
@@ -341,39 +289,150 @@ impl InputGrid {
             println!();
         }
     }
-}
 
-fn uadd(a: usize, b: i64) -> usize {
-    ((a as i64) + b) as usize
+    /// Draw every tile as 3×3 characters, with the pipe running through the middle.
+    /// Pipes only reach the edge of their tile where they connect, so the gaps that
+    /// you can squeeze between show up as blank space. The loop is drawn with heavy
+    /// lines, and every other tile is marked `I` or `O` for inside or outside.
+    fn squeeze(&self, tiles: &HashSet<Pos>, inside: &HashSet<Pos>) -> String {
+        let mut out = vec![vec![' '; self.grid.width() * 3]; self.grid.height() * 3];
+        let start = self.start_position();
+        for (pos, pipe) in self.grid.iter() {
+            let Pos(x, y) = pos;
+            let (cx, cy) = (x * 3 + 1, y * 3 + 1);
+            let on_loop = tiles.contains(&pos);
+            let pipe = if on_loop { self.pipe_at(pos) } else { *pipe };
+
+            out[cy][cx] = match (on_loop, inside.contains(&pos)) {
+                _ if pos == start => 'S',
+                (true, _) => pipe.heavy(),
+                (false, true) => 'I',
+                (false, false) => 'O',
+            };
+            if let Some((d0, d1)) = pipe.connection_directions() {
+                for dir in [d0, d1] {
+                    let (dx, dy) = dir.offset();
+                    let arm = match (dir, on_loop) {
+                        (Direction::North | Direction::South, true) => '┃',
+                        (Direction::North | Direction::South, false) => '│',
+                        (_, true) => '━',
+                        (_, false) => '─',
+                    };
+                    out[(cy as i64 + dy) as usize][(cx as i64 + dx) as usize] = arm;
+                }
+            }
+        }
+        out.iter().map(|row| row.iter().join("") + "\n").join("")
+    }
+
+    /// Draw the maze as an SVG: inside tiles are green and outside tiles are grey,
+    /// with any junk pipes drawn thin, the loop drawn thick, and the start as a red dot
+    fn svg(&self, tiles: &[Pos], inside: &HashSet<Pos>) -> String {
+        const TILE: usize = 10;
+        let center = |Pos(x, y): Pos| (x * TILE + TILE / 2, y * TILE + TILE / 2);
+        let on_loop: HashSet<Pos> = tiles.iter().copied().collect();
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {} {}">"#,
+            self.grid.width() * TILE,
+            self.grid.height() * TILE
+        )
+        .unwrap();
+        for (pos, pipe) in self.grid.iter() {
+            let Pos(x, y) = pos;
+            let fill = match (on_loop.contains(&pos), inside.contains(&pos)) {
+                (true, _) => "white",
+                (false, true) => "#7fc97f",
+                (false, false) => "#dddddd",
+            };
+            writeln!(
+                svg,
+                r#"  <rect x="{}" y="{}" width="{TILE}" height="{TILE}" fill="{fill}"/>"#,
+                x * TILE,
+                y * TILE
+            )
+            .unwrap();
+            if let (false, Some((d0, d1))) = (on_loop.contains(&pos), pipe.connection_directions())
+            {
+                let (cx, cy) = center(pos);
+                let [(dx0, dy0), (dx1, dy1)] = [d0, d1].map(|dir| dir.offset());
+                let half = (TILE / 2) as i64;
+                writeln!(
+                    svg,
+                    r##"  <polyline points="{},{} {cx},{cy} {},{}" fill="none" stroke="#999999" stroke-width="1"/>"##,
+                    cx as i64 + dx0 * half,
+                    cy as i64 + dy0 * half,
+                    cx as i64 + dx1 * half,
+                    cy as i64 + dy1 * half
+                )
+                .unwrap();
+            }
+        }
+
+        let points = tiles
+            .iter()
+            .map(|pos| {
+                let (x, y) = center(*pos);
+                format!("{x},{y}")
+            })
+            .join(" ");
+        writeln!(
+            svg,
+            r##"  <polygon points="{points}" fill="none" stroke="#1f4e9e" stroke-width="3"/>"##
+        )
+        .unwrap();
+        let (sx, sy) = center(self.start_position());
+        writeln!(
+            svg,
+            r#"  <circle cx="{sx}" cy="{sy}" r="{}" fill="red"/>"#,
+            TILE / 3
+        )
+        .unwrap();
+        svg.push_str("</svg>\n");
+        svg
+    }
 }
 
 impl Solution for Day10 {
     fn part1(input: &str) -> Option<i64> {
         let grid: InputGrid = input.parse().unwrap();
-
-        let path = grid.find_path();
-        path.iter()
-            .enumerate()
-            .map(|(i, pos)| cmp::min(i + 1, path.len() - i))
-            .max()
-            .map(|inner| inner as i64)
+        match grid.find_loop() {
+            // going both ways around the loop at once, we meet halfway
+            Ok(tiles) => Some(tiles.len() as i64 / 2),
+            Err(err) => {
+                eprintln!("{err}");
+                None
+            }
+        }
     }
 
     fn part2(input: &str) -> Option<i64> {
+        Self::part2_with(input, &Options::default())
+    }
+
+    /// Pass `-o display` to print the maze in colour, `-o svg=FILE` to draw it into an
+    /// SVG file, or `-o squeeze` to print it scaled up 3 times so that the gaps between
+    /// pipes can be seen
+    fn part2_with(input: &str, opts: &Options) -> Option<i64> {
         let grid: InputGrid = input.parse().unwrap();
-        let path: Vec<Pos> = grid.find_path();
-        let dir = grid
-            .next_position(grid.start_position(), Direction::Nope)
-            .unwrap();
-        // let path = grid.find_path().into_iter().rev().collect_vec();
-        // let dir = Direction::South;
+        let path = match grid.find_loop() {
+            Ok(tiles) => tiles,
+            Err(err) => {
+                eprintln!("{err}");
+                return None;
+            }
+        };
+        let start = grid.start_position();
+        let dir = grid.direction_to(start, path[0])?;
 
         let winding_number = grid.winding_number(&path, dir);
+        let walls: HashSet<Pos> = path.iter().copied().collect();
 
-        let (dir, mut inside) = path
-            .iter()
-            .map(|Pos(x, y)| (Pos(*x, *y), &grid.grid[*y][*x]))
-            .fold((dir, vec![]), |(dir, mut hist), (Pos(x, y), pipe)| {
+        let (_, mut inside) = path.iter().map(|pos| (*pos, grid.pipe_at(*pos))).fold(
+            (dir, vec![]),
+            |(dir, mut hist), (pos, pipe)| {
                 let exit_dir = match (pipe, -dir) {
                     (Pipe::Vertical, Direction::North) => Direction::South,
                     (Pipe::Vertical, Direction::South) => Direction::North,
@@ -384,41 +443,41 @@ impl Solution for Day10 {
                     (Pipe::Horizontal, _) => unreachable!(),
 
                     (Pipe::NorthEastBend, Direction::North) => {
-                        hist.push(Pos(uadd(x, -winding_number), uadd(y, 0)));
+                        hist.extend(grid.grid.offset(pos, (-winding_number, 0)));
                         Direction::East
                     }
                     (Pipe::NorthEastBend, Direction::East) => {
-                        hist.push(Pos(uadd(x, 0), uadd(y, -winding_number)));
+                        hist.extend(grid.grid.offset(pos, (0, -winding_number)));
                         Direction::North
                     }
                     (Pipe::NorthEastBend, dir) => unreachable!("north east {dir:?}"),
 
                     (Pipe::NorthWestBend, Direction::North) => {
-                        hist.push(Pos(uadd(x, -winding_number), uadd(y, 0)));
+                        hist.extend(grid.grid.offset(pos, (-winding_number, 0)));
                         Direction::West
                     }
                     (Pipe::NorthWestBend, Direction::West) => {
-                        hist.push(Pos(uadd(x, 0), uadd(y, winding_number)));
+                        hist.extend(grid.grid.offset(pos, (0, winding_number)));
                         Direction::North
                     }
                     (Pipe::NorthWestBend, dir) => unreachable!("north west {dir:?}"),
 
                     (Pipe::SouthWestBend, Direction::South) => {
-                        hist.push(Pos(uadd(x, winding_number), uadd(y, 0)));
+                        hist.extend(grid.grid.offset(pos, (winding_number, 0)));
                         Direction::West
                     }
                     (Pipe::SouthWestBend, Direction::West) => {
-                        hist.push(Pos(uadd(x, 0), uadd(y, winding_number)));
+                        hist.extend(grid.grid.offset(pos, (0, winding_number)));
                         Direction::South
                     }
                     (Pipe::SouthWestBend, dir) => unreachable!("south west {dir:?}"),
 
                     (Pipe::SouthEastBend, Direction::East) => {
-                        hist.push(Pos(uadd(x, 0), uadd(y, -winding_number)));
+                        hist.extend(grid.grid.offset(pos, (0, -winding_number)));
                         Direction::South
                     }
                     (Pipe::SouthEastBend, Direction::South) => {
-                        hist.push(Pos(uadd(x, winding_number), uadd(y, 0)));
+                        hist.extend(grid.grid.offset(pos, (winding_number, 0)));
                         Direction::East
                     }
                     (Pipe::SouthEastBend, dir) => unreachable!("south east {dir:?}"),
@@ -426,42 +485,139 @@ impl Solution for Day10 {
                     (Pipe::Ground, _) | (Pipe::Start, _) => unreachable!(),
                 };
                 match exit_dir {
-                    Direction::North => hist.push(Pos(uadd(x, winding_number), uadd(y, 0))),
-                    Direction::South => hist.push(Pos(uadd(x, -winding_number), uadd(y, 0))),
-                    Direction::East => hist.push(Pos(uadd(x, 0), uadd(y, winding_number))),
-                    Direction::West => hist.push(Pos(uadd(x, 0), uadd(y, -winding_number))),
-                    _ => (),
+                    Direction::North => hist.extend(grid.grid.offset(pos, (winding_number, 0))),
+                    Direction::South => hist.extend(grid.grid.offset(pos, (-winding_number, 0))),
+                    Direction::East => hist.extend(grid.grid.offset(pos, (0, winding_number))),
+                    Direction::West => hist.extend(grid.grid.offset(pos, (0, -winding_number))),
                 }
                 (exit_dir, hist)
-            });
+            },
+        );
         inside = inside
             .into_iter()
-            .filter(|Pos(x, y)| x <= &grid.width && y <= &grid.height)
-            .filter(|pos| pos != &grid.start_position())
-            .filter(|pos| !path.contains(pos))
+            .filter(|pos| !walls.contains(pos))
             .unique()
             .collect_vec();
 
-        // flood the inside points that we have
-        let mut worklist = inside.clone();
-        while let Some(item) = worklist.pop() {
-            // check if the four surrounding points should be included, if they haven't been already
-            let Pos(x, y) = item;
-            let candidates = [
-                Pos(x, y.saturating_sub(1)),
-                Pos(x, cmp::min(y + 1, grid.height)),
-                Pos(cmp::min(x + 1, grid.width), y),
-                Pos(x.saturating_sub(1), y),
-            ];
-            for c in candidates {
-                if !path.contains(&c) && !inside.contains(&c) {
-                    inside.push(c);
-                    worklist.push(c);
-                }
-            }
+        // flood the inside points that we have, without crossing the loop
+        let inside = bfs(
+            inside,
+            |pos| {
+                grid.grid
+                    .neighbors4(*pos)
+                    .filter(|c| !walls.contains(c))
+                    .collect_vec()
+            },
+            |_| false,
+        )
+        .dist
+        .into_keys()
+        .collect_vec();
+
+        let inside_set: HashSet<Pos> = inside.iter().copied().collect();
+        if opts.flag("display") {
+            grid.display(&walls, &inside_set);
+        }
+        if let Some(file) = opts.get::<String>("svg") {
+            std::fs::write(&file, grid.svg(&path, &inside_set)).expect("Unable to write SVG");
+        }
+        if opts.flag("squeeze") {
+            print!("{}", grid.squeeze(&walls, &inside_set));
         }
 
-        grid.display_with_path(&path, &inside);
+        let by_area = grid.inside_by_area(&path);
+        if by_area != inside.len() as i64 {
+            eprintln!(
+                "Flood fill found {} tiles inside the loop, but its area says {by_area}",
+                inside.len()
+            );
+        }
         Some(inside.len() as i64)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find_loop(input: &str) -> Result<Vec<Pos>, LoopError> {
+        input.parse::<InputGrid>().unwrap().find_loop()
+    }
+
+    #[test]
+    fn start_in_corner() {
+        let input = "S-7\n|.|\nL-J";
+        assert_eq!(Day10::part1(input), Some(4));
+        assert_eq!(Day10::part2(input), Some(1));
+    }
+
+    #[test]
+    fn start_on_bottom_right_edge() {
+        let input = "F-7\n|.|\nL-S";
+        assert_eq!(Day10::part1(input), Some(4));
+        assert_eq!(Day10::part2(input), Some(1));
+    }
+
+    #[test]
+    fn start_on_left_edge() {
+        let input = "..F7.\n.FJ|.\nSJ.L7\n|F--J\nLJ...";
+        assert_eq!(Day10::part1(input), Some(8));
+        assert_eq!(Day10::part2(input), Some(1));
+    }
+
+    #[test]
+    fn junk_pipes_pointing_at_start() {
+        // north and west of the start are dead ends that connect to it
+        let input = ".|...\n-S-7.\n.|.|.\n.L-J.";
+        assert_eq!(find_loop(input).unwrap().len(), 8);
+        assert_eq!(Day10::part1(input), Some(4));
+        assert_eq!(Day10::part2(input), Some(1));
+    }
+
+    #[test]
+    fn junk_pipes_around_loop() {
+        let input = "-L|F7\n7S-7|\nL|7||\n-L-J|\nL|-JF";
+        assert_eq!(Day10::part1(input), Some(4));
+        assert_eq!(Day10::part2(input), Some(1));
+    }
+
+    #[test]
+    fn start_pipe_is_inferred() {
+        let grid: InputGrid = ".|...\n-S-7.\n.|.|.\n.L-J.".parse().unwrap();
+        assert_eq!(grid.start_pipe(), Some(Pipe::SouthEastBend));
+    }
+
+    #[test]
+    fn broken_loop() {
+        assert_eq!(find_loop("S-7\n|..\nL-J"), Err(LoopError::NoLoop));
+        assert_eq!(Day10::part1("S-7\n|..\nL-J"), None);
+        assert_eq!(Day10::part2("S-7\n|..\nL-J"), None);
+    }
+
+    #[test]
+    fn lone_start() {
+        assert_eq!(find_loop("..\n.S"), Err(LoopError::NoLoop));
+    }
+
+    #[test]
+    fn no_start() {
+        assert_eq!(find_loop("F7\nLJ"), Err(LoopError::NoStart));
+    }
+
+    #[test]
+    fn inside_counts_agree() {
+        let input = "...........\n\
+                     .S-------7.\n\
+                     .|F-----7|.\n\
+                     .||.....||.\n\
+                     .||.....||.\n\
+                     .|L-7.F-J|.\n\
+                     .|..|.|..|.\n\
+                     .L--J.L--J.\n\
+                     ...........";
+        let grid: InputGrid = input.parse().unwrap();
+        let tiles = grid.find_loop().unwrap();
+        assert_eq!(grid.inside_by_area(&tiles), 4);
+        assert_eq!(Day10::part2(input), Some(4));
+    }
+}
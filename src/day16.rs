@@ -1,13 +1,236 @@
-use crate::Solution;
+use std::{fmt::Display, str::FromStr};
+
+use color_print::cprint;
+use itertools::Itertools;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::{
+    grid::{Direction, FromChar, Grid, Pos},
+    Options, Solution,
+};
 
 pub struct Day16;
 
+#[derive(Debug)]
+struct Contraption {
+    grid: Grid<Tile>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Tile {
+    Empty,
+    /// `/`
+    ForwardMirror,
+    /// `\`
+    BackMirror,
+    /// `|`
+    VerticalSplitter,
+    /// `-`
+    HorizontalSplitter,
+}
+
+/// A beam sitting on tile `pos`, about to move in `dir`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Beam {
+    pos: Pos,
+    dir: Direction,
+}
+
+/// Which tiles got energized by a beam. For every tile, we remember the
+/// directions that a beam has already passed through it in as a bitmask.
+/// Seeing the same (position, direction) pair twice means that we are in a
+/// loop, and that beam can stop.
+struct Energized(Grid<u8>);
+
+// == Parsing ==
+
+impl FromStr for Contraption {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let grid: Grid<Tile> = s.parse()?;
+        if grid.width() == 0 {
+            return Err("Empty grid");
+        }
+        Ok(Contraption { grid })
+    }
+}
+
+impl FromChar for Tile {
+    fn from_char(value: char) -> Result<Self, &'static str> {
+        match value {
+            '.' => Ok(Tile::Empty),
+            '/' => Ok(Tile::ForwardMirror),
+            '\\' => Ok(Tile::BackMirror),
+            '|' => Ok(Tile::VerticalSplitter),
+            '-' => Ok(Tile::HorizontalSplitter),
+            _ => Err("Unknown tile"),
+        }
+    }
+}
+
+impl Display for Tile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Tile::Empty => write!(f, "."),
+            Tile::ForwardMirror => write!(f, "/"),
+            Tile::BackMirror => write!(f, "\\"),
+            Tile::VerticalSplitter => write!(f, "|"),
+            Tile::HorizontalSplitter => write!(f, "-"),
+        }
+    }
+}
+
+// == Solution code ==
+
+impl Beam {
+    fn mask(&self) -> u8 {
+        match self.dir {
+            Direction::North => 0b0001,
+            Direction::East => 0b0010,
+            Direction::South => 0b0100,
+            Direction::West => 0b1000,
+        }
+    }
+}
+
+impl Tile {
+    /// The directions a beam leaves this tile in, when it enters moving in `dir`
+    fn redirect(&self, dir: Direction) -> (Direction, Option<Direction>) {
+        use Direction::*;
+        match (self, dir) {
+            (Tile::Empty, _) => (dir, None),
+
+            (Tile::ForwardMirror, North | South) => (dir.turn_right(), None),
+            (Tile::ForwardMirror, East | West) => (dir.turn_left(), None),
+
+            (Tile::BackMirror, North | South) => (dir.turn_left(), None),
+            (Tile::BackMirror, East | West) => (dir.turn_right(), None),
+
+            (Tile::VerticalSplitter, West | East) => (North, Some(South)),
+            (Tile::VerticalSplitter, North | South) => (dir, None),
+
+            (Tile::HorizontalSplitter, North | South) => (West, Some(East)),
+            (Tile::HorizontalSplitter, West | East) => (dir, None),
+        }
+    }
+}
+
+impl Contraption {
+    /// Move one step in `dir` from `pos`, unless that would leave the grid
+    fn step(&self, pos: Pos, dir: Direction) -> Option<Beam> {
+        let pos = self.grid.step(pos, dir)?;
+        Some(Beam { pos, dir })
+    }
+
+    /// Send a beam into the contraption and record every tile that it passes through.
+    /// `start` is the first tile that the beam enters.
+    fn energize(&self, start: Beam) -> Energized {
+        let mut seen = Grid::new(self.grid.width(), self.grid.height(), 0u8);
+        let mut worklist = vec![start];
+
+        while let Some(beam) = worklist.pop() {
+            if seen[beam.pos] & beam.mask() != 0 {
+                continue;
+            }
+            seen[beam.pos] |= beam.mask();
+
+            let (out, split) = self.grid[beam.pos].redirect(beam.dir);
+            worklist.extend(self.step(beam.pos, out));
+            if let Some(split) = split {
+                worklist.extend(self.step(beam.pos, split));
+            }
+        }
+
+        Energized(seen)
+    }
+
+    /// All the beams that enter the contraption from one of its edges
+    fn edge_beams(&self) -> Vec<Beam> {
+        let (w, h) = (self.grid.width(), self.grid.height());
+        (0..w)
+            .flat_map(|x| {
+                [
+                    Beam {
+                        pos: Pos(x, 0),
+                        dir: Direction::South,
+                    },
+                    Beam {
+                        pos: Pos(x, h - 1),
+                        dir: Direction::North,
+                    },
+                ]
+            })
+            .chain((0..h).flat_map(|y| {
+                [
+                    Beam {
+                        pos: Pos(0, y),
+                        dir: Direction::East,
+                    },
+                    Beam {
+                        pos: Pos(w - 1, y),
+                        dir: Direction::West,
+                    },
+                ]
+            }))
+            .collect_vec()
+    }
+
+    fn display_energized(&self, energized: &Energized) {
+        for (row, seen_row) in self.grid.rows().zip(energized.0.rows()) {
+            for (tile, seen) in row.iter().zip(seen_row) {
+                match (tile, *seen != 0) {
+                    (Tile::Empty, true) => cprint!("<yellow>#</>"),
+                    (_, true) => cprint!("<yellow,bold>{tile}</>"),
+                    (_, false) => cprint!("<dim>{tile}</>"),
+                }
+            }
+            println!();
+        }
+    }
+}
+
+impl Energized {
+    fn count(&self) -> usize {
+        self.0.iter().filter(|(_, seen)| **seen != 0).count()
+    }
+}
+
 impl Solution for Day16 {
     fn part1(input: &str) -> Option<i64> {
-        None
+        Self::part1_with(input, &Options::default())
     }
 
     fn part2(input: &str) -> Option<i64> {
-        None
+        Self::part2_with(input, &Options::default())
+    }
+
+    /// Pass `-o display` to print the energized tiles
+    fn part1_with(input: &str, opts: &Options) -> Option<i64> {
+        let contraption: Contraption = input.parse().unwrap();
+        let energized = contraption.energize(Beam {
+            pos: Pos(0, 0),
+            dir: Direction::East,
+        });
+        if opts.flag("display") {
+            contraption.display_energized(&energized);
+        }
+        Some(energized.count() as i64)
+    }
+
+    fn part2_with(input: &str, opts: &Options) -> Option<i64> {
+        let contraption: Contraption = input.parse().unwrap();
+        let (_, energized) = contraption
+            .edge_beams()
+            .into_par_iter()
+            .map(|beam| {
+                let energized = contraption.energize(beam);
+                (energized.count(), energized)
+            })
+            .max_by_key(|(count, _)| *count)?;
+        if opts.flag("display") {
+            contraption.display_energized(&energized);
+        }
+        Some(energized.count() as i64)
     }
 }
@@ -1,13 +1,148 @@
-use crate::Solution;
+use std::{cmp::Ordering, str::FromStr};
+
+use color_print::cprint;
+use itertools::Itertools;
+
+use crate::{
+    grid::{Direction, Grid, Pos},
+    search::dijkstra,
+    Options, Solution,
+};
 
 pub struct Day17;
 
+#[derive(Debug)]
+struct City {
+    grid: Grid<u32>,
+}
+
+/// A search state. The crucible is standing on `pos`, having just moved `run`
+/// blocks in a straight line in `dir`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct State {
+    pos: Pos,
+    dir: Direction,
+    run: usize,
+}
+
+/// The cheapest route that was found through the city
+#[derive(Debug)]
+struct Route {
+    heat_loss: u32,
+    /// every block visited, including the start and the end
+    path: Vec<Pos>,
+}
+
+// == Parsing ==
+
+impl FromStr for City {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let grid: Grid<u32> = s.parse()?;
+        if grid.width() == 0 {
+            return Err("Empty grid");
+        }
+        Ok(City { grid })
+    }
+}
+
+// == Solution code ==
+
+impl City {
+    /// The states that we can reach in one move from `state`, given that the
+    /// crucible has to move between `min_run` and `max_run` blocks before turning.
+    fn successors(
+        &self,
+        state: State,
+        min_run: usize,
+        max_run: usize,
+    ) -> impl Iterator<Item = State> + '_ {
+        [state.dir, state.dir.turn_left(), state.dir.turn_right()]
+            .into_iter()
+            .filter_map(move |dir| {
+                let run = if dir == state.dir { state.run + 1 } else { 1 };
+                // the start state has a run of 0, so it can turn straight away
+                let can_turn = state.run == 0 || state.run >= min_run;
+                if run > max_run || (dir != state.dir && !can_turn) {
+                    return None;
+                }
+                let pos = self.grid.step(state.pos, dir)?;
+                Some(State { pos, dir, run })
+            })
+    }
+
+    /// Dijkstra from the top left to the bottom right block, where the search
+    /// state includes the direction and the length of the current straight run.
+    fn cheapest_route(&self, min_run: usize, max_run: usize) -> Option<Route> {
+        let goal = Pos(self.grid.width() - 1, self.grid.height() - 1);
+        let start = State {
+            pos: Pos(0, 0),
+            dir: Direction::East,
+            run: 0,
+        };
+
+        let search = dijkstra(
+            start,
+            |state| {
+                self.successors(*state, min_run, max_run)
+                    .map(|next| (next, self.grid[next.pos]))
+            },
+            |state| state.pos == goal && state.run >= min_run,
+        );
+        Some(Route {
+            heat_loss: *search.cost()?,
+            path: search.path()?.iter().map(|state| state.pos).collect(),
+        })
+    }
+
+    fn display_route(&self, route: &Route) {
+        let path = route.path.iter().copied().tuple_windows().collect_vec();
+        for (y, row) in self.grid.rows().enumerate() {
+            for (x, loss) in row.iter().enumerate() {
+                let arrow = path.iter().find_map(|(from, to)| {
+                    (*to == Pos(x, y)).then_some(match (to.0.cmp(&from.0), to.1.cmp(&from.1)) {
+                        (Ordering::Greater, _) => '>',
+                        (Ordering::Less, _) => '<',
+                        (_, Ordering::Greater) => 'v',
+                        _ => '^',
+                    })
+                });
+                match arrow {
+                    Some(arrow) => cprint!("<red,bold>{arrow}</>"),
+                    None => print!("{loss}"),
+                }
+            }
+            println!();
+        }
+    }
+
+    fn solve(&self, min_run: usize, max_run: usize, opts: &Options) -> Option<i64> {
+        let route = self.cheapest_route(min_run, max_run)?;
+        if opts.flag("display") {
+            self.display_route(&route);
+        }
+        Some(route.heat_loss as i64)
+    }
+}
+
 impl Solution for Day17 {
     fn part1(input: &str) -> Option<i64> {
-        None
+        Self::part1_with(input, &Options::default())
     }
 
     fn part2(input: &str) -> Option<i64> {
-        None
+        Self::part2_with(input, &Options::default())
+    }
+
+    /// Pass `-o display` to print the route that was taken
+    fn part1_with(input: &str, opts: &Options) -> Option<i64> {
+        let city: City = input.parse().unwrap();
+        city.solve(1, 3, opts)
+    }
+
+    fn part2_with(input: &str, opts: &Options) -> Option<i64> {
+        let city: City = input.parse().unwrap();
+        city.solve(4, 10, opts)
     }
 }
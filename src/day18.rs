@@ -1,13 +1,146 @@
+use std::str::FromStr;
+
+use itertools::Itertools;
+
 use crate::Solution;
 
 pub struct Day18;
 
+#[derive(Debug)]
+struct DigPlan(Vec<Instruction>);
+
+/// One line of the dig plan, like `R 6 (#70c710)`. Part 1 uses `dir` and
+/// `distance`, part 2 reads the real instruction out of the hex `color`.
+#[derive(Debug)]
+struct Instruction {
+    dir: Direction,
+    distance: i64,
+    color: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+// == Parsing ==
+
+impl FromStr for DigPlan {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(DigPlan(
+            s.lines()
+                .map(|line| line.parse())
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_whitespace().collect_vec().as_slice() {
+            [dir, distance, color] => Ok(Instruction {
+                dir: dir.parse()?,
+                distance: distance.parse().map_err(|_| "Invalid distance")?,
+                color: color
+                    .strip_prefix("(#")
+                    .and_then(|c| c.strip_suffix(')'))
+                    .ok_or("Invalid color")?
+                    .to_string(),
+            }),
+            _ => Err("Malformed instruction"),
+        }
+    }
+}
+
+impl FromStr for Direction {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "U" | "3" => Ok(Direction::Up),
+            "D" | "1" => Ok(Direction::Down),
+            "L" | "2" => Ok(Direction::Left),
+            "R" | "0" => Ok(Direction::Right),
+            _ => Err("Unknown direction"),
+        }
+    }
+}
+
+// == Solution code ==
+
+impl Instruction {
+    /// Decode the instruction hidden in the color. The first five hex digits are
+    /// the distance, the last one is the direction.
+    fn decode_color(&self) -> Result<(Direction, i64), &'static str> {
+        if self.color.len() != 6 {
+            return Err("Color should have 6 hex digits");
+        }
+        let (distance, dir) = self.color.split_at(5);
+        Ok((
+            dir.parse()?,
+            i64::from_str_radix(distance, 16).map_err(|_| "Invalid hex distance")?,
+        ))
+    }
+}
+
+impl Direction {
+    fn offset(&self) -> (i64, i64) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
+}
+
+/// Compute the number of cubic meters of lava that the lagoon dug out by
+/// the given instructions holds.
+///
+/// The trench is a polygon through the centers of the dug squares. The shoelace
+/// formula gives us its area `A`, and Pick's theorem `A = i + b/2 - 1` gives
+/// us the number of interior points `i` from that and the number of boundary
+/// points `b`, which is just the length of the trench. The lagoon is the
+/// interior plus the trench itself, so it holds `i + b = A + b/2 + 1`.
+fn lagoon_volume(instructions: impl Iterator<Item = (Direction, i64)>) -> i64 {
+    let (_, twice_area, boundary) = instructions.fold(
+        ((0i64, 0i64), 0i128, 0i64),
+        |((x, y), twice_area, boundary), (dir, distance)| {
+            let (dx, dy) = dir.offset();
+            let (nx, ny) = (x + dx * distance, y + dy * distance);
+            (
+                (nx, ny),
+                twice_area + (x as i128 * ny as i128 - nx as i128 * y as i128),
+                boundary + distance,
+            )
+        },
+    );
+    let area = twice_area.abs() / 2;
+    (area + boundary as i128 / 2 + 1) as i64
+}
+
 impl Solution for Day18 {
     fn part1(input: &str) -> Option<i64> {
-        None
+        let DigPlan(plan) = input.parse().unwrap();
+        Some(lagoon_volume(
+            plan.iter().map(|inst| (inst.dir, inst.distance)),
+        ))
     }
 
     fn part2(input: &str) -> Option<i64> {
-        None
+        let DigPlan(plan) = input.parse().unwrap();
+        let decoded: Vec<_> = plan
+            .iter()
+            .map(|inst| inst.decode_color())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        Some(lagoon_volume(decoded.into_iter()))
     }
 }
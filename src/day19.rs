@@ -1,13 +1,339 @@
+use std::{cmp::Ordering, collections::HashMap, fmt::Display, str::FromStr};
+
+use itertools::Itertools;
+
 use crate::Solution;
 
 pub struct Day19;
 
+#[derive(Debug)]
+struct Input(System, Vec<Part>);
+
+/// All of the workflows, by name
+#[derive(Debug)]
+struct System(HashMap<String, Workflow>);
+
+/// A workflow like `px{a<2006:qkq,m>2090:A,rfg}`
+#[derive(Debug)]
+struct Workflow {
+    name: String,
+    rules: Vec<Rule>,
+    fallback: Target,
+}
+
+/// A single conditional rule, like `a<2006:qkq`
+#[derive(Debug)]
+struct Rule {
+    category: Category,
+    /// `Less` for `<` and `Greater` for `>`
+    cmp: Ordering,
+    value: u64,
+    target: Target,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Target {
+    Accept,
+    Reject,
+    Workflow(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Category {
+    X,
+    M,
+    A,
+    S,
+}
+
+/// The ratings of a part, indexed by `Category`
+#[derive(Debug)]
+struct Part([u64; 4]);
+
+/// A 4 dimensional box of parts. Every category has a half-open range of ratings.
+#[derive(Debug, Clone, Copy)]
+struct PartRange([(u64, u64); 4]);
+
+#[derive(Debug, PartialEq, Eq)]
+enum WorkflowError {
+    /// Some rule sends parts to a workflow that doesn't exist
+    Unknown(String),
+    /// Parts keep coming back to the same workflow. Holds the workflows on the loop.
+    Cycle(Vec<String>),
+}
+
+// == Parsing ==
+
+impl FromStr for Input {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (workflows, parts) = s.split_once("\n\n").ok_or("Missing parts section")?;
+        Ok(Input(
+            workflows.parse()?,
+            parts
+                .lines()
+                .map(|line| line.parse())
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+}
+
+impl FromStr for System {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(System(
+            s.lines()
+                .map(|line| line.parse::<Workflow>())
+                .map_ok(|wf| (wf.name.to_string(), wf))
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+}
+
+impl FromStr for Workflow {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, rest) = s.split_once('{').ok_or("Missing '{' in workflow")?;
+        let rules = rest.strip_suffix('}').ok_or("Missing '}' in workflow")?;
+        match rules.split(',').collect_vec().as_slice() {
+            [rules @ .., fallback] => Ok(Workflow {
+                name: name.to_string(),
+                rules: rules
+                    .iter()
+                    .map(|rule| rule.parse())
+                    .collect::<Result<_, _>>()?,
+                fallback: fallback.parse()?,
+            }),
+            [] => Err("Workflow without rules"),
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (cond, target) = s.split_once(':').ok_or("Missing ':' in rule")?;
+        let (category, value) = cond.split_at(cond.find(['<', '>']).ok_or("Missing comparison")?);
+        let (cmp, value) = value.split_at(1);
+        Ok(Rule {
+            category: category.parse()?,
+            cmp: if cmp == "<" {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            },
+            value: value.parse().map_err(|_| "Invalid rule value")?,
+            target: target.parse()?,
+        })
+    }
+}
+
+impl FromStr for Target {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "A" => Ok(Target::Accept),
+            "R" => Ok(Target::Reject),
+            "" => Err("Empty target"),
+            name => Ok(Target::Workflow(name.to_string())),
+        }
+    }
+}
+
+impl FromStr for Category {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "x" => Ok(Category::X),
+            "m" => Ok(Category::M),
+            "a" => Ok(Category::A),
+            "s" => Ok(Category::S),
+            _ => Err("Unknown category"),
+        }
+    }
+}
+
+impl FromStr for Part {
+    type Err = &'static str;
+
+    /// Input like `{x=787,m=2655,a=1222,s=2876}`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut ratings = [0; 4];
+        for rating in s
+            .strip_prefix('{')
+            .and_then(|s| s.strip_suffix('}'))
+            .ok_or("Part should be wrapped in {}")?
+            .split(',')
+        {
+            let (category, value) = rating.split_once('=').ok_or("Missing '=' in rating")?;
+            ratings[category.parse::<Category>()? as usize] =
+                value.parse().map_err(|_| "Invalid rating")?;
+        }
+        Ok(Part(ratings))
+    }
+}
+
+impl Display for WorkflowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorkflowError::Unknown(name) => write!(f, "Unknown workflow {name:?}"),
+            WorkflowError::Cycle(names) => write!(f, "Workflows loop: {}", names.join(" -> ")),
+        }
+    }
+}
+
+// == Solution code ==
+
+impl Rule {
+    fn matches(&self, part: &Part) -> bool {
+        part.0[self.category as usize].cmp(&self.value) == self.cmp
+    }
+
+    /// Split `range` into the part that matches this rule, and the part that doesn't
+    fn split(&self, range: PartRange) -> (Option<PartRange>, Option<PartRange>) {
+        let (lo, hi) = range.0[self.category as usize];
+        // `< value` holds for the ratings in [lo, value), and `> value` for [value + 1, hi)
+        let mid = match self.cmp {
+            Ordering::Less => self.value,
+            _ => self.value + 1,
+        }
+        .clamp(lo, hi);
+
+        let lower = range.with(self.category, (lo, mid));
+        let upper = range.with(self.category, (mid, hi));
+        match self.cmp {
+            Ordering::Less => (lower, upper),
+            _ => (upper, lower),
+        }
+    }
+}
+
+impl PartRange {
+    fn full(lo: u64, hi: u64) -> Self {
+        PartRange([(lo, hi); 4])
+    }
+
+    /// Replace the range of one category, if that leaves the box non-empty
+    fn with(mut self, category: Category, range: (u64, u64)) -> Option<Self> {
+        self.0[category as usize] = range;
+        (range.0 < range.1).then_some(self)
+    }
+
+    fn combinations(&self) -> u64 {
+        self.0.iter().map(|(lo, hi)| hi - lo).product()
+    }
+}
+
+impl System {
+    fn get(&self, name: &str) -> Result<&Workflow, WorkflowError> {
+        self.0
+            .get(name)
+            .ok_or_else(|| WorkflowError::Unknown(name.to_string()))
+    }
+
+    /// Run a part through the workflows, starting at `in`
+    fn accepts(&self, part: &Part) -> Result<bool, WorkflowError> {
+        let mut seen = vec![];
+        let mut workflow = self.get("in")?;
+        loop {
+            if seen.contains(&&workflow.name) {
+                seen.push(&workflow.name);
+                return Err(WorkflowError::Cycle(seen.into_iter().cloned().collect()));
+            }
+            seen.push(&workflow.name);
+
+            let target = workflow
+                .rules
+                .iter()
+                .find(|rule| rule.matches(part))
+                .map_or(&workflow.fallback, |rule| &rule.target);
+            match target {
+                Target::Accept => return Ok(true),
+                Target::Reject => return Ok(false),
+                Target::Workflow(name) => workflow = self.get(name)?,
+            }
+        }
+    }
+
+    /// Push a whole box of parts through the workflows at once, splitting it up
+    /// at every rule, and count how many of the parts end up being accepted.
+    fn count_accepted(&self, range: PartRange) -> Result<u64, WorkflowError> {
+        self.count_accepted_from(range, "in", &mut vec![])
+    }
+
+    /// `path` holds the workflows that led to this one. If any box comes back to one
+    /// of those, every part in it will keep going around that loop forever.
+    fn count_accepted_from<'a>(
+        &'a self,
+        range: PartRange,
+        name: &'a str,
+        path: &mut Vec<&'a str>,
+    ) -> Result<u64, WorkflowError> {
+        if path.contains(&name) {
+            path.push(name);
+            return Err(WorkflowError::Cycle(
+                path.iter().map(|name| name.to_string()).collect(),
+            ));
+        }
+        let workflow = self.get(name)?;
+        path.push(name);
+
+        let mut total = 0;
+        let mut rest = Some(range);
+        let branches = workflow
+            .rules
+            .iter()
+            .map(|rule| (Some(rule), &rule.target))
+            .chain([(None, &workflow.fallback)]);
+        for (rule, target) in branches {
+            let Some(range) = rest else { break };
+            let (matched, unmatched) = match rule {
+                Some(rule) => rule.split(range),
+                None => (Some(range), None),
+            };
+            rest = unmatched;
+            if let Some(matched) = matched {
+                total += match target {
+                    Target::Accept => matched.combinations(),
+                    Target::Reject => 0,
+                    Target::Workflow(next) => self.count_accepted_from(matched, next, path)?,
+                };
+            }
+        }
+
+        path.pop();
+        Ok(total)
+    }
+}
+
 impl Solution for Day19 {
     fn part1(input: &str) -> Option<i64> {
-        None
+        let Input(system, parts) = input.parse().unwrap();
+        parts
+            .iter()
+            .filter_map(|part| match system.accepts(part) {
+                Ok(true) => Some(Ok(part.0.iter().sum::<u64>())),
+                Ok(false) => None,
+                Err(e) => Some(Err(e)),
+            })
+            .sum::<Result<u64, _>>()
+            .map_err(|e| eprintln!("{e}"))
+            .ok()
+            .map(|total| total as i64)
     }
 
     fn part2(input: &str) -> Option<i64> {
-        None
+        let Input(system, _) = input.parse().unwrap();
+        system
+            .count_accepted(PartRange::full(1, 4001))
+            .map_err(|e| eprintln!("{e}"))
+            .ok()
+            .map(|total| total as i64)
     }
 }
@@ -1,8 +1,8 @@
-use std::{cmp, str::FromStr};
+use std::{collections::BTreeMap, fmt::Display, str::FromStr};
 
 use itertools::Itertools;
 
-use crate::Solution;
+use crate::{Options, Solution};
 
 pub struct Day2;
 
@@ -14,15 +14,12 @@ struct Game {
 
 #[derive(Debug)]
 struct Round {
-    draws: Vec<(usize, Color)>,
+    draws: Vec<(usize, String)>,
 }
 
-#[derive(Debug)]
-enum Color {
-    Red,
-    Blue,
-    Green,
-}
+/// A number of cubes for every colour. Colours that aren't mentioned have no cubes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Bag(BTreeMap<String, usize>);
 
 impl FromStr for Game {
     type Err = &'static str;
@@ -47,82 +44,176 @@ impl FromStr for Round {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let draws = s
             .split(", ")
-            .map(|x| {
+            .flat_map(|x| {
                 x.split_whitespace()
                     .tuples()
-                    .map(|(number, color)| (number.parse().unwrap(), color.parse().unwrap()))
-                    .collect::<Vec<(usize, Color)>>()
+                    .map(|(number, color)| (number.parse().unwrap(), color.to_string()))
+                    .collect::<Vec<(usize, String)>>()
             })
-            .flatten()
             .collect::<Vec<_>>();
         Ok(Self { draws })
     }
 }
 
-impl FromStr for Color {
+impl FromStr for Bag {
     type Err = &'static str;
 
+    /// Input like `12 red, 13 green, 14 blue`, the same as a round
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "red" => Ok(Self::Red),
-            "blue" => Ok(Self::Blue),
-            "green" => Ok(Self::Green),
-            _ => Err("Unknown Color"),
+        Ok(s.parse::<Round>()?.counts())
+    }
+}
+
+impl Display for Bag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.0
+                .iter()
+                .map(|(color, count)| format!("{count} {color}"))
+                .join(", ")
+        )
+    }
+}
+
+impl Display for Round {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.draws
+                .iter()
+                .map(|(count, color)| format!("{count} {color}"))
+                .join(", ")
+        )
+    }
+}
+
+impl Bag {
+    fn get(&self, color: &str) -> usize {
+        self.0.get(color).copied().unwrap_or(0)
+    }
+
+    /// Does this bag have at least as many cubes of every colour as `other`?
+    fn covers(&self, other: &Bag) -> bool {
+        other
+            .0
+            .iter()
+            .all(|(color, count)| self.get(color) >= *count)
+    }
+
+    /// The smallest bag that covers both bags
+    fn union(mut self, other: &Bag) -> Bag {
+        for (color, count) in &other.0 {
+            let entry = self.0.entry(color.to_string()).or_default();
+            *entry = (*entry).max(*count);
         }
+        self
     }
 }
 
 impl Game {
-    fn min_possible(&self) -> (usize, usize, usize) {
-        self.rounds.iter().fold((0, 0, 0), |(r, g, b), round| {
-            let (rc, gc, bc) = round.counts();
-            (cmp::max(r, rc), cmp::max(g, gc), cmp::max(b, bc))
-        })
+    /// The game is possible with exactly the bags that cover this one, so this
+    /// describes every bag that the game could have been played with
+    fn feasible_region(&self) -> Bag {
+        self.rounds
+            .iter()
+            .fold(Bag::default(), |bag, round| bag.union(&round.counts()))
     }
 
-    fn possible_with(&self, red: usize, green: usize, blue: usize) -> bool {
+    fn possible_with(&self, bag: &Bag) -> bool {
+        bag.covers(&self.feasible_region())
+    }
+
+    /// The first round that couldn't have been drawn from `bag`, if there is one
+    fn impossible_round(&self, bag: &Bag) -> Option<(usize, &Round)> {
         self.rounds
             .iter()
-            .all(|r| r.possible_with(red, green, blue))
+            .enumerate()
+            .find(|(_, round)| !bag.covers(&round.counts()))
     }
 }
 
 impl Round {
-    fn counts(&self) -> (usize, usize, usize) {
-        self.draws
-            .iter()
-            .fold((0, 0, 0), |(r, g, b), (count, color)| match color {
-                Color::Red => (r + count, g, b),
-                Color::Green => (r, g + count, b),
-                Color::Blue => (r, g, b + count),
-            })
-    }
-
-    fn possible_with(&self, red: usize, green: usize, blue: usize) -> bool {
-        let (red_count, green_count, blue_count) = self.counts();
-        red_count <= red && green_count <= green && blue_count <= blue
+    fn counts(&self) -> Bag {
+        let mut bag = Bag::default();
+        for (count, color) in &self.draws {
+            *bag.0.entry(color.to_string()).or_default() += count;
+        }
+        bag
     }
 }
 
 impl Solution for Day2 {
     fn part1(input: &str) -> Option<i64> {
+        Self::part1_with(input, &Options::default())
+    }
+
+    fn part2(input: &str) -> Option<i64> {
+        Self::part2_with(input, &Options::default())
+    }
+
+    /// Pass `-o "bag=12 red, 13 green, 14 blue"` to use another bag, and `-o show`
+    /// to see which games are possible, or the round that rules each game out
+    fn part1_with(input: &str, opts: &Options) -> Option<i64> {
+        let bag = opts
+            .get::<Bag>("bag")
+            .unwrap_or_else(|| "12 red, 13 green, 14 blue".parse().unwrap());
+        let games = input
+            .lines()
+            .map(|line| line.parse::<Game>().unwrap())
+            .collect_vec();
+        if opts.flag("show") {
+            for game in &games {
+                match game.impossible_round(&bag) {
+                    None => println!("Game {} is possible", game.id),
+                    Some((i, round)) => println!(
+                        "Game {} is impossible because of round {}: {round}",
+                        game.id,
+                        i + 1
+                    ),
+                }
+            }
+        }
         Some(
-            input
-                .lines()
-                .map(|line| line.parse::<Game>().unwrap())
-                .filter_map(|game| game.possible_with(12, 13, 14).then_some(game.id))
+            games
+                .iter()
+                .filter_map(|game| game.possible_with(&bag).then_some(game.id))
                 .sum::<usize>() as i64,
         )
     }
 
-    fn part2(input: &str) -> Option<i64> {
+    /// Pass `-o show` to see the fewest cubes that each game needs, and the smallest
+    /// bag that makes every game possible
+    fn part2_with(input: &str, opts: &Options) -> Option<i64> {
+        let games = input
+            .lines()
+            .map(|line| line.parse::<Game>().unwrap())
+            .collect_vec();
+        let regions = games
+            .iter()
+            .map(|game| game.feasible_region())
+            .collect_vec();
+        // the smallest bag for all of the games also tells us every colour there is
+        let smallest = regions.iter().fold(Bag::default(), Bag::union);
+        if opts.flag("show") {
+            for (game, region) in games.iter().zip(&regions) {
+                println!("Game {} needs at least {region}", game.id);
+            }
+            println!("Every game is possible with {smallest}");
+        }
+
+        // a colour that a game never shows might as well have no cubes
         Some(
-            input
-                .lines()
-                .map(|line| line.parse::<Game>().unwrap())
-                .map(|game| {
-                    let (r, g, b) = game.min_possible();
-                    r * g * b
+            regions
+                .iter()
+                .map(|region| {
+                    smallest
+                        .0
+                        .keys()
+                        .map(|color| region.get(color))
+                        .product::<usize>()
                 })
                 .sum::<usize>() as i64,
         )
@@ -1,13 +1,244 @@
-use crate::Solution;
+use std::{
+    collections::{HashMap, VecDeque},
+    str::FromStr,
+};
+
+use itertools::Itertools;
+
+use crate::{math::crt, Options, Solution};
 
 pub struct Day20;
 
+#[derive(Debug, Clone)]
+struct Network(HashMap<String, Module>);
+
+#[derive(Debug, Clone)]
+struct Module {
+    name: String,
+    kind: Kind,
+    outputs: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Kind {
+    Broadcaster,
+    /// `%`, remembers whether it is on
+    FlipFlop(bool),
+    /// `&`, remembers the last pulse from each of its inputs
+    Conjunction(HashMap<String, Pulse>),
+    /// Modules that are only ever sent to, like `output` or `rx`
+    Sink,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pulse {
+    Low,
+    High,
+}
+
+// == Parsing ==
+
+impl FromStr for Network {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modules: HashMap<String, Module> = s
+            .lines()
+            .map(|line| line.parse::<Module>())
+            .map_ok(|module| (module.name.to_string(), module))
+            .collect::<Result<_, _>>()?;
+
+        // add the modules that nobody declared, and let every conjunction know about its inputs
+        let edges = modules
+            .values()
+            .flat_map(|m| {
+                m.outputs
+                    .iter()
+                    .map(|out| (m.name.to_string(), out.to_string()))
+            })
+            .collect_vec();
+        for (from, to) in edges {
+            let module = modules.entry(to.to_string()).or_insert_with(|| Module {
+                name: to,
+                kind: Kind::Sink,
+                outputs: vec![],
+            });
+            if let Kind::Conjunction(memory) = &mut module.kind {
+                memory.insert(from, Pulse::Low);
+            }
+        }
+
+        Ok(Network(modules))
+    }
+}
+
+impl FromStr for Module {
+    type Err = &'static str;
+
+    /// Input like `%a -> inv, con`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, outputs) = s.split_once(" -> ").ok_or("Missing '->'")?;
+        let (kind, name) = match name.split_at_checked(1).ok_or("Missing module name")? {
+            ("%", name) => (Kind::FlipFlop(false), name),
+            ("&", name) => (Kind::Conjunction(HashMap::new()), name),
+            _ if name == "broadcaster" => (Kind::Broadcaster, name),
+            _ => return Err("Unknown module type"),
+        };
+        Ok(Module {
+            name: name.to_string(),
+            kind,
+            outputs: outputs.split(", ").map(|out| out.to_string()).collect(),
+        })
+    }
+}
+
+// == Solution code ==
+
+impl Module {
+    /// Handle a pulse coming in from `from`. Returns the pulse to send to all outputs, if any.
+    fn receive(&mut self, from: &str, pulse: Pulse) -> Option<Pulse> {
+        match (&mut self.kind, pulse) {
+            (Kind::Broadcaster, _) => Some(pulse),
+            (Kind::FlipFlop(_), Pulse::High) => None,
+            (Kind::FlipFlop(on), Pulse::Low) => {
+                *on = !*on;
+                Some(if *on { Pulse::High } else { Pulse::Low })
+            }
+            (Kind::Conjunction(memory), _) => {
+                memory.insert(from.to_string(), pulse);
+                if memory.values().all(|p| *p == Pulse::High) {
+                    Some(Pulse::Low)
+                } else {
+                    Some(Pulse::High)
+                }
+            }
+            (Kind::Sink, _) => None,
+        }
+    }
+}
+
+impl Network {
+    /// Push the button once and process pulses in the order they were sent.
+    /// `observe` gets to see every pulse as `(from, to, pulse)`.
+    fn press(&mut self, mut observe: impl FnMut(&str, &str, Pulse)) {
+        let mut queue =
+            VecDeque::from([("button".to_string(), "broadcaster".to_string(), Pulse::Low)]);
+        while let Some((from, to, pulse)) = queue.pop_front() {
+            observe(&from, &to, pulse);
+            let Some(module) = self.0.get_mut(&to) else {
+                continue;
+            };
+            if let Some(out) = module.receive(&from, pulse) {
+                for next in &module.outputs {
+                    queue.push_back((to.to_string(), next.to_string(), out));
+                }
+            }
+        }
+    }
+
+    fn inputs_of<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Module> {
+        self.0
+            .values()
+            .filter(move |m| m.outputs.iter().any(|out| out == name))
+    }
+
+    /// Print the module graph in graphviz format
+    fn dot(&self) {
+        println!("digraph {{");
+        for module in self.0.values().sorted_by_key(|m| &m.name) {
+            let shape = match module.kind {
+                Kind::Broadcaster => "doublecircle",
+                Kind::FlipFlop(_) => "box",
+                Kind::Conjunction(_) => "invtrapezium",
+                Kind::Sink => "circle",
+            };
+            println!("  {} [shape={shape}];", module.name);
+            for out in &module.outputs {
+                println!("  {} -> {out};", module.name);
+            }
+        }
+        println!("}}");
+    }
+}
+
 impl Solution for Day20 {
     fn part1(input: &str) -> Option<i64> {
-        None
+        Self::part1_with(input, &Options::default())
+    }
+
+    /// Pass `-o dot` to print the module graph for graphviz, in either part
+    fn part1_with(input: &str, opts: &Options) -> Option<i64> {
+        let mut network: Network = input.parse().unwrap();
+        if opts.flag("dot") {
+            network.dot();
+        }
+
+        let (mut low, mut high) = (0, 0);
+        for _ in 0..1000 {
+            network.press(|_, _, pulse| match pulse {
+                Pulse::Low => low += 1,
+                Pulse::High => high += 1,
+            });
+        }
+        Some(low * high)
     }
 
     fn part2(input: &str) -> Option<i64> {
-        None
+        Self::part2_with(input, &Options::default())
+    }
+
+    fn part2_with(input: &str, opts: &Options) -> Option<i64> {
+        let mut network: Network = input.parse().unwrap();
+        if opts.flag("dot") {
+            network.dot();
+        }
+
+        // `rx` is fed by a single conjunction, which only sends a low pulse once all of
+        // its inputs have sent it a high pulse during the same press. Each of those inputs
+        // is the end of an independent counter that fires on a fixed cycle, so we find
+        // when every counter first fires and how long its cycle is, and line them up.
+        let feeder = network.inputs_of("rx").exactly_one().ok()?;
+        if !matches!(feeder.kind, Kind::Conjunction(_)) {
+            return None;
+        }
+        let feeder = feeder.name.to_string();
+        let mut fired: HashMap<String, Vec<u64>> = network
+            .inputs_of(&feeder)
+            .map(|m| (m.name.to_string(), vec![]))
+            .collect();
+
+        // give up if some counter doesn't fire twice
+        let found = (1..=1_000_000).any(|presses| {
+            network.press(|from, to, pulse| {
+                if to == feeder && pulse == Pulse::High {
+                    fired.entry(from.to_string()).and_modify(|hits| {
+                        if hits.last() != Some(&presses) && hits.len() < 2 {
+                            hits.push(presses);
+                        }
+                    });
+                }
+            });
+            fired.values().all(|hits| hits.len() == 2)
+        });
+        if !found {
+            eprintln!("Some input of {feeder} didn't fire twice");
+            return None;
+        }
+
+        // counter `i` fires on presses `first + k * cycle`, for every k >= 0
+        let counters = fired
+            .values()
+            .map(|hits| (hits[0] as i128, (hits[1] - hits[0]) as i128))
+            .collect_vec();
+        let Some((x, m)) = crt(counters
+            .iter()
+            .map(|(first, cycle)| (first.rem_euclid(*cycle), *cycle)))
+        else {
+            eprintln!("The inputs of {feeder} never all fire on the same press");
+            return None;
+        };
+        // the first solution that comes after every counter's first press
+        let latest = counters.iter().map(|(first, _)| *first).max()?;
+        Some((latest + (x - latest).rem_euclid(m)) as i64)
     }
 }
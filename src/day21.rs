@@ -1,13 +1,149 @@
-use crate::Solution;
+use std::{collections::HashSet, str::FromStr};
+
+use itertools::Itertools;
+use num::ToPrimitive;
+
+use crate::{
+    day9::Sequence,
+    grid::{Direction, Grid, Pos},
+    Options, Solution,
+};
 
 pub struct Day21;
 
+#[derive(Debug)]
+struct Garden {
+    rocks: Grid<bool>,
+    start: (i64, i64),
+}
+
+// == Parsing ==
+
+impl FromStr for Garden {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tiles: Grid<char> = s.parse()?;
+        if tiles.width() == 0 {
+            return Err("Empty garden");
+        }
+        if tiles.iter().any(|(_, c)| !"#.S".contains(*c)) {
+            return Err("Unknown tile");
+        }
+        let Pos(x, y) = tiles
+            .position(|c| *c == 'S')
+            .ok_or("No starting position")?;
+        Ok(Garden {
+            rocks: tiles.map(|c| *c == '#'),
+            start: (x as i64, y as i64),
+        })
+    }
+}
+
+// == Solution code ==
+
+impl Garden {
+    /// Is `(x, y)` a garden plot? When `infinite` is set, the map repeats in every direction.
+    fn is_plot(&self, (x, y): (i64, i64), infinite: bool) -> bool {
+        let (w, h) = (self.rocks.width() as i64, self.rocks.height() as i64);
+        let inside = (0..w).contains(&x) && (0..h).contains(&y);
+        (infinite || inside) && !self.rocks[self.rocks.wrapping_offset(Pos(0, 0), (x, y))]
+    }
+
+    /// BFS out from the start. Entry `t` of the result is the number of plots that
+    /// can be reached in exactly `t` steps, for every `t` up to `max_steps`.
+    ///
+    /// The elf can always waste two steps by stepping back and forth, so a plot is
+    /// reachable in exactly `t` steps if its distance is at most `t` and has the same
+    /// parity as `t`.
+    fn reachable(&self, max_steps: usize, infinite: bool) -> Vec<u64> {
+        let mut seen = HashSet::from([self.start]);
+        let mut frontier = vec![self.start];
+        // number of plots at each distance
+        let mut at_distance = vec![1u64];
+
+        for _ in 0..max_steps {
+            frontier = frontier
+                .into_iter()
+                .flat_map(|(x, y)| {
+                    Direction::ALL.map(|dir| (x + dir.offset().0, y + dir.offset().1))
+                })
+                .filter(|pos| self.is_plot(*pos, infinite))
+                .filter(|pos| seen.insert(*pos))
+                .collect_vec();
+            at_distance.push(frontier.len() as u64);
+        }
+
+        let mut totals = [0, 0];
+        at_distance
+            .iter()
+            .enumerate()
+            .map(|(t, n)| {
+                totals[t % 2] += n;
+                totals[t % 2]
+            })
+            .collect()
+    }
+
+    /// Count the plots reachable in exactly `steps` steps on the infinitely repeating map.
+    ///
+    /// Every `width` steps, the reachable area grows by another ring of maps, so
+    /// sampling the count at `steps % width + width * k` gives a sequence that
+    /// (eventually) grows quadratically in `k`. We find a few samples by brute force,
+    /// check that the sequence has settled into a quadratic, and then extrapolate it
+    /// out to the `k` that we want.
+    fn reachable_infinite(&self, steps: usize) -> Option<u64> {
+        const SAMPLES: usize = 10;
+
+        let size = self.rocks.width();
+        let (offset, k) = (steps % size, steps / size);
+        let counts = self.reachable(offset + size * (SAMPLES - 1).min(k), true);
+        if k < SAMPLES {
+            return Some(counts[steps]);
+        }
+
+        let samples = (0..SAMPLES)
+            .map(|k| counts[offset + size * k] as i64)
+            .collect_vec();
+        let settled = samples[SAMPLES - 6..]
+            .iter()
+            .tuple_windows()
+            .all(|(a, b, c, d)| d - 3 * c + 3 * b - a == 0);
+        if !settled {
+            return None;
+        }
+
+        Sequence(samples[SAMPLES - 3..].to_vec())
+            .value_at((k - (SAMPLES - 3)) as i64)
+            .to_u64()
+    }
+}
+
 impl Solution for Day21 {
     fn part1(input: &str) -> Option<i64> {
-        None
+        Self::part1_with(input, &Options::default())
     }
 
     fn part2(input: &str) -> Option<i64> {
-        None
+        Self::part2_with(input, &Options::default())
+    }
+
+    /// Pass `-o steps=6` to change the number of steps, for example for the short input
+    fn part1_with(input: &str, opts: &Options) -> Option<i64> {
+        let garden: Garden = input.parse().unwrap();
+        let steps = opts.get("steps").unwrap_or(64);
+        garden
+            .reachable(steps, false)
+            .last()
+            .map(|count| *count as i64)
+    }
+
+    fn part2_with(input: &str, opts: &Options) -> Option<i64> {
+        let garden: Garden = input.parse().unwrap();
+        if garden.rocks.width() != garden.rocks.height() {
+            return None;
+        }
+        let steps = opts.get("steps").unwrap_or(26501365);
+        garden.reachable_infinite(steps).map(|count| count as i64)
     }
 }
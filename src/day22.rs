@@ -1,13 +1,168 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    str::FromStr,
+};
+
+use itertools::Itertools;
+
 use crate::Solution;
 
 pub struct Day22;
 
+#[derive(Debug)]
+struct Snapshot(Vec<Brick>);
+
+/// A brick, given by two opposite corners. `start` is the corner with the
+/// smaller coordinates.
+#[derive(Debug, Clone, Copy)]
+struct Brick {
+    start: (usize, usize, usize),
+    end: (usize, usize, usize),
+}
+
+/// The bricks after they have settled, and who is resting on whom
+#[derive(Debug)]
+struct Stack {
+    /// `supports[i]` are the bricks directly on top of brick `i`
+    supports: Vec<HashSet<usize>>,
+    /// `supported_by[i]` are the bricks directly below brick `i`
+    supported_by: Vec<HashSet<usize>>,
+}
+
+// == Parsing ==
+
+impl FromStr for Snapshot {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Snapshot(
+            s.lines()
+                .map(|line| line.parse())
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+}
+
+impl FromStr for Brick {
+    type Err = &'static str;
+
+    /// Input like `1,0,1~1,2,1`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s.split_once('~').ok_or("Missing '~'")?;
+        let parse = |corner: &str| {
+            corner
+                .split(',')
+                .map(|n| n.parse::<usize>().map_err(|_| "Invalid coordinate"))
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .collect_tuple::<(_, _, _)>()
+                .ok_or("Corner should have 3 coordinates")
+        };
+        let (a, b) = (parse(start)?, parse(end)?);
+        Ok(Brick {
+            start: (a.0.min(b.0), a.1.min(b.1), a.2.min(b.2)),
+            end: (a.0.max(b.0), a.1.max(b.1), a.2.max(b.2)),
+        })
+    }
+}
+
+// == Solution code ==
+
+impl Brick {
+    /// All the `(x, y)` columns that this brick covers
+    fn footprint(&self) -> impl Iterator<Item = (usize, usize)> {
+        (self.start.0..=self.end.0).cartesian_product(self.start.1..=self.end.1)
+    }
+
+    fn height(&self) -> usize {
+        self.end.2 - self.start.2 + 1
+    }
+}
+
+impl Snapshot {
+    /// Drop all of the bricks, lowest first. For every column, the height map
+    /// remembers the top of the highest brick in that column so far, and which brick
+    /// that is. A brick comes to rest on the highest of those under its footprint, and
+    /// is supported by every brick that reaches exactly that height.
+    fn settle(&self) -> Stack {
+        let order = (0..self.0.len())
+            .sorted_by_key(|i| self.0[*i].start.2)
+            .collect_vec();
+
+        let mut height_map: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+        let mut supports = vec![HashSet::new(); self.0.len()];
+        let mut supported_by = vec![HashSet::new(); self.0.len()];
+
+        for i in order {
+            let brick = &self.0[i];
+            let below = brick
+                .footprint()
+                .filter_map(|col| height_map.get(&col))
+                .copied()
+                .collect_vec();
+            let rest = below.iter().map(|(top, _)| *top).max().unwrap_or(0);
+            for (_, j) in below.iter().filter(|(top, _)| *top == rest) {
+                supports[*j].insert(i);
+                supported_by[i].insert(*j);
+            }
+
+            let top = rest + brick.height();
+            for col in brick.footprint() {
+                height_map.insert(col, (top, i));
+            }
+        }
+
+        Stack {
+            supports,
+            supported_by,
+        }
+    }
+}
+
+impl Stack {
+    /// A brick can be removed if everything it supports has another brick to lean on
+    fn can_disintegrate(&self, i: usize) -> bool {
+        self.supports[i]
+            .iter()
+            .all(|j| self.supported_by[*j].len() > 1)
+    }
+
+    /// How many other bricks fall when brick `i` is removed. Instead of settling
+    /// everything again, propagate through the support graph: a brick falls once
+    /// every brick that it rests on has fallen.
+    fn chain_reaction(&self, i: usize) -> usize {
+        let mut fallen = HashSet::from([i]);
+        let mut queue = VecDeque::from([i]);
+        while let Some(brick) = queue.pop_front() {
+            for above in &self.supports[brick] {
+                if !fallen.contains(above) && self.supported_by[*above].is_subset(&fallen) {
+                    fallen.insert(*above);
+                    queue.push_back(*above);
+                }
+            }
+        }
+        fallen.len() - 1
+    }
+}
+
 impl Solution for Day22 {
     fn part1(input: &str) -> Option<i64> {
-        None
+        let snapshot: Snapshot = input.parse().unwrap();
+        let stack = snapshot.settle();
+        Some(
+            (0..snapshot.0.len())
+                .filter(|i| stack.can_disintegrate(*i))
+                .count() as i64,
+        )
     }
 
     fn part2(input: &str) -> Option<i64> {
-        None
+        let snapshot: Snapshot = input.parse().unwrap();
+        let stack = snapshot.settle();
+        Some(
+            (0..snapshot.0.len())
+                .map(|i| stack.chain_reaction(i))
+                .sum::<usize>() as i64,
+        )
     }
 }
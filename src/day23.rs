@@ -1,13 +1,226 @@
-use crate::Solution;
+use std::{collections::HashMap, str::FromStr};
+
+use itertools::Itertools;
+
+use crate::{
+    grid::{Direction, FromChar, Grid, Pos},
+    Options, Solution,
+};
 
 pub struct Day23;
 
+#[derive(Debug)]
+struct TrailMap {
+    grid: Grid<Tile>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Tile {
+    Forest,
+    Path,
+    Slope(Direction),
+}
+
+/// The trail map squashed down to the junctions, where the hike can actually make
+/// a choice. Every edge is a stretch of trail without any choices, weighted by its length.
+#[derive(Debug)]
+struct Graph {
+    junctions: Vec<Pos>,
+    /// `edges[i]` holds `(j, length)` for every junction `j` that can be reached from `i`
+    edges: Vec<Vec<(usize, usize)>>,
+    start: usize,
+    end: usize,
+}
+
+// == Parsing ==
+
+impl FromStr for TrailMap {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let grid: Grid<Tile> = s.parse()?;
+        if grid.width() == 0 {
+            return Err("Empty map");
+        }
+        Ok(TrailMap { grid })
+    }
+}
+
+impl FromChar for Tile {
+    fn from_char(value: char) -> Result<Self, &'static str> {
+        match value {
+            '#' => Ok(Tile::Forest),
+            '.' => Ok(Tile::Path),
+            '^' => Ok(Tile::Slope(Direction::North)),
+            '>' => Ok(Tile::Slope(Direction::East)),
+            'v' => Ok(Tile::Slope(Direction::South)),
+            '<' => Ok(Tile::Slope(Direction::West)),
+            _ => Err("Unknown tile"),
+        }
+    }
+}
+
+// == Solution code ==
+
+impl TrailMap {
+    /// The neighbor of `pos` in `dir`, unless it is forest or off the map
+    fn step(&self, pos: Pos, dir: Direction) -> Option<Pos> {
+        let next = self.grid.step(pos, dir)?;
+        (self.grid[next] != Tile::Forest).then_some(next)
+    }
+
+    /// The open tiles next to `pos`, with the direction to get there. If `slippery` is
+    /// set, standing on a slope means that you have to go downhill.
+    fn moves(&self, pos: Pos, slippery: bool) -> impl Iterator<Item = (Direction, Pos)> + '_ {
+        Direction::ALL
+            .into_iter()
+            .filter_map(move |dir| match self.grid[pos] {
+                Tile::Slope(downhill) if slippery && downhill != dir => None,
+                _ => self.step(pos, dir).map(|next| (dir, next)),
+            })
+    }
+
+    /// The open tile in the given row
+    fn opening(&self, y: usize) -> Option<Pos> {
+        self.grid
+            .row(y)
+            .iter()
+            .position(|tile| *tile != Tile::Forest)
+            .map(|x| Pos(x, y))
+    }
+
+    /// Find every junction (any open tile that doesn't have exactly two open neighbors)
+    /// and walk each trail leaving a junction until it reaches the next one.
+    fn compress(&self, slippery: bool) -> Option<Graph> {
+        let start = self.opening(0)?;
+        let end = self.opening(self.grid.height() - 1)?;
+
+        let junctions = self
+            .grid
+            .positions()
+            .filter(|pos| self.grid[*pos] != Tile::Forest)
+            .filter(|pos| self.moves(*pos, false).count() != 2)
+            .collect_vec();
+        let index: HashMap<Pos, usize> = junctions
+            .iter()
+            .enumerate()
+            .map(|(i, pos)| (*pos, i))
+            .collect();
+
+        let edges = junctions
+            .iter()
+            .map(|junction| {
+                self.moves(*junction, slippery)
+                    .filter_map(|(_, first)| {
+                        let (mut prev, mut curr, mut length) = (*junction, first, 1);
+                        while !index.contains_key(&curr) {
+                            // a trail tile has exactly one way forward that isn't backward
+                            let (_, next) =
+                                self.moves(curr, slippery).find(|(_, next)| *next != prev)?;
+                            (prev, curr, length) = (curr, next, length + 1);
+                        }
+                        Some((index[&curr], length))
+                    })
+                    .collect_vec()
+            })
+            .collect_vec();
+
+        Some(Graph {
+            start: index[&start],
+            end: index[&end],
+            junctions,
+            edges,
+        })
+    }
+
+    fn solve(&self, slippery: bool, opts: &Options) -> Option<i64> {
+        let graph = self.compress(slippery)?;
+        if opts.flag("dot") {
+            graph.dot();
+        }
+        graph.longest_hike().map(|length| length as i64)
+    }
+}
+
+impl Graph {
+    /// The longest hike from start to end that doesn't visit any junction twice.
+    ///
+    /// This is a plain DFS over every simple path, with the visited junctions kept
+    /// in a bitset. The end has only one neighbor, and once we reach that neighbor
+    /// we have to go straight to the end (going anywhere else would cut us off),
+    /// so we stop the search there.
+    fn longest_hike(&self) -> Option<usize> {
+        if self.junctions.len() > 128 {
+            eprintln!(
+                "There are {} junctions, but the search can only keep track of 128",
+                self.junctions.len()
+            );
+            return None;
+        }
+        let (goal, extra) = match self
+            .edges
+            .iter()
+            .enumerate()
+            .flat_map(|(i, edges)| edges.iter().map(move |(j, len)| (i, *j, *len)))
+            .filter(|(_, j, _)| *j == self.end)
+            .exactly_one()
+        {
+            Ok((i, _, len)) => (i, len),
+            Err(_) => (self.end, 0),
+        };
+        self.dfs(self.start, goal, 1u128 << self.start)
+            .map(|length| length + extra)
+    }
+
+    fn dfs(&self, from: usize, goal: usize, visited: u128) -> Option<usize> {
+        if from == goal {
+            return Some(0);
+        }
+        self.edges[from]
+            .iter()
+            .filter(|(next, _)| visited & (1 << next) == 0)
+            .filter_map(|(next, len)| {
+                self.dfs(*next, goal, visited | (1 << next))
+                    .map(|rest| rest + len)
+            })
+            .max()
+    }
+
+    /// Print the junction graph in graphviz format
+    fn dot(&self) {
+        println!("digraph {{");
+        for (i, Pos(x, y)) in self.junctions.iter().enumerate() {
+            let shape = if i == self.start || i == self.end {
+                "doublecircle"
+            } else {
+                "circle"
+            };
+            println!("  n{i} [label=\"({x},{y})\", shape={shape}];");
+            for (j, len) in &self.edges[i] {
+                println!("  n{i} -> n{j} [label={len}];");
+            }
+        }
+        println!("}}");
+    }
+}
+
 impl Solution for Day23 {
     fn part1(input: &str) -> Option<i64> {
-        None
+        Self::part1_with(input, &Options::default())
     }
 
     fn part2(input: &str) -> Option<i64> {
-        None
+        Self::part2_with(input, &Options::default())
+    }
+
+    /// Pass `-o dot` to print the compressed junction graph for graphviz
+    fn part1_with(input: &str, opts: &Options) -> Option<i64> {
+        let map: TrailMap = input.parse().unwrap();
+        map.solve(true, opts)
+    }
+
+    fn part2_with(input: &str, opts: &Options) -> Option<i64> {
+        let map: TrailMap = input.parse().unwrap();
+        map.solve(false, opts)
     }
 }
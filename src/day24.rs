@@ -1,13 +1,185 @@
-use crate::Solution;
+use std::str::FromStr;
+
+use itertools::Itertools;
+use num::{BigInt, BigRational, ToPrimitive, Zero};
+
+use crate::{Options, Solution};
 
 pub struct Day24;
 
+#[derive(Debug)]
+struct Hail(Vec<Hailstone>);
+
+#[derive(Debug, Clone, Copy)]
+struct Hailstone {
+    pos: [i64; 3],
+    vel: [i64; 3],
+}
+
+// == Parsing ==
+
+impl FromStr for Hail {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Hail(
+            s.lines()
+                .map(|line| line.parse())
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+}
+
+impl FromStr for Hailstone {
+    type Err = &'static str;
+
+    /// Input like `19, 13, 30 @ -2,  1, -2`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (pos, vel) = s.split_once('@').ok_or("Missing '@'")?;
+        let parse = |triple: &str| -> Result<[i64; 3], Self::Err> {
+            triple
+                .split(',')
+                .map(|n| n.trim().parse().map_err(|_| "Invalid number"))
+                .collect::<Result<Vec<_>, _>>()?
+                .try_into()
+                .map_err(|_| "Expected 3 coordinates")
+        };
+        Ok(Hailstone {
+            pos: parse(pos)?,
+            vel: parse(vel)?,
+        })
+    }
+}
+
+// == Solution code ==
+
+impl Hailstone {
+    /// Do the paths of these two hailstones cross inside `[lo, hi]` in the x and y
+    /// axes, at a point in the future for both of them?
+    ///
+    /// Solving `p1 + t * v1 = p2 + s * v2` gives `t = ((p2 - p1) x v2) / (v1 x v2)`
+    /// and `s = ((p2 - p1) x v1) / (v1 x v2)`. Instead of dividing, we keep everything
+    /// as numerator and denominator. With coordinates around 10^14 and velocities
+    /// around 10^3, every product we compare stays well within an `i128`.
+    fn paths_cross_in(&self, other: &Hailstone, lo: i128, hi: i128) -> bool {
+        let [px1, py1, _] = self.pos.map(|n| n as i128);
+        let [vx1, vy1, _] = self.vel.map(|n| n as i128);
+        let [px2, py2, _] = other.pos.map(|n| n as i128);
+        let [vx2, vy2, _] = other.vel.map(|n| n as i128);
+
+        let mut det = vx1 * vy2 - vy1 * vx2;
+        if det == 0 {
+            // parallel paths never cross. (The puzzle doesn't have any that are
+            // on the same line, and it isn't clear what that would mean anyway.)
+            return false;
+        }
+        let (dx, dy) = (px2 - px1, py2 - py1);
+        let mut t = dx * vy2 - dy * vx2;
+        let mut s = dx * vy1 - dy * vx1;
+        // make the denominator positive, so that comparisons don't flip
+        if det < 0 {
+            (det, t, s) = (-det, -t, -s);
+        }
+        if t < 0 || s < 0 {
+            return false;
+        }
+
+        // the crossing point, scaled up by `det`
+        let x = px1 * det + t * vx1;
+        let y = py1 * det + t * vy1;
+        (lo * det..=hi * det).contains(&x) && (lo * det..=hi * det).contains(&y)
+    }
+}
+
+/// Solve the square system `a * x = b` exactly, with Gaussian elimination over
+/// the rationals. Returns `None` if the system is singular.
+fn solve_linear(mut a: Vec<Vec<BigRational>>, mut b: Vec<BigRational>) -> Option<Vec<BigRational>> {
+    let n = b.len();
+    for col in 0..n {
+        let pivot = (col..n).find(|row| !a[*row][col].is_zero())?;
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        let (pivot_row, pivot_b) = (a[col].clone(), b[col].clone());
+        for row in (0..n).filter(|row| *row != col) {
+            if !a[row][col].is_zero() {
+                let factor = &a[row][col] / &pivot_row[col];
+                for (x, p) in a[row].iter_mut().zip(&pivot_row).skip(col) {
+                    *x -= &factor * p;
+                }
+                b[row] -= &factor * &pivot_b;
+            }
+        }
+    }
+    Some((0..n).map(|i| &b[i] / &a[i][i]).collect())
+}
+
+impl Hail {
+    /// Find the position and velocity of a rock that hits every hailstone.
+    ///
+    /// For the rock at `P` with velocity `V` to hit hailstone `i` at some time `t`,
+    /// `P - p_i` and `V - v_i` have to be parallel, so `(P - p_i) x (V - v_i) = 0`.
+    /// Expanding that, the only non-linear part is `P x V`, which is the same for every
+    /// hailstone. Subtracting the equations for two different hailstones cancels it,
+    /// which leaves linear equations in the 6 unknowns. Two pairs of hailstones,
+    /// and the three planes of the cross product, give us 6 equations.
+    fn throw_rock(&self) -> Option<([BigRational; 3], [BigRational; 3])> {
+        self.0.iter().tuple_combinations().find_map(|(h0, h1, h2)| {
+            let (a, b): (Vec<_>, Vec<_>) = [(0, 1), (0, 2), (1, 2)]
+                .into_iter()
+                .flat_map(|plane| [(plane, h0, h1), (plane, h0, h2)])
+                .map(|((i, j), hi, hj)| {
+                    let (pi, vi, pj, vj) = (hi.pos, hi.vel, hj.pos, hj.vel);
+                    // unknowns are ordered [Px, Py, Pz, Vx, Vy, Vz]
+                    let mut row = [0i64; 6];
+                    row[i] = vi[j] - vj[j];
+                    row[j] = vj[i] - vi[i];
+                    row[3 + i] = pj[j] - pi[j];
+                    row[3 + j] = pi[i] - pj[i];
+                    let cross = |p: [i64; 3], v: [i64; 3]| {
+                        BigInt::from(p[i]) * v[j] - BigInt::from(p[j]) * v[i]
+                    };
+                    let rhs = cross(pi, vi) - cross(pj, vj);
+                    (
+                        row.map(|n| BigRational::from_integer(n.into())).to_vec(),
+                        BigRational::from_integer(rhs),
+                    )
+                })
+                .unzip();
+            let x = solve_linear(a, b)?;
+            Some((
+                [x[0].clone(), x[1].clone(), x[2].clone()],
+                [x[3].clone(), x[4].clone(), x[5].clone()],
+            ))
+        })
+    }
+}
+
 impl Solution for Day24 {
     fn part1(input: &str) -> Option<i64> {
-        None
+        Self::part1_with(input, &Options::default())
+    }
+
+    /// Pass `-o area_min=7 -o area_max=27` to change the test area, for the short input
+    fn part1_with(input: &str, opts: &Options) -> Option<i64> {
+        let Hail(hail) = input.parse().unwrap();
+        let lo = opts.get("area_min").unwrap_or(200000000000000);
+        let hi = opts.get("area_max").unwrap_or(400000000000000);
+        Some(
+            hail.iter()
+                .tuple_combinations()
+                .filter(|(h1, h2)| h1.paths_cross_in(h2, lo, hi))
+                .count() as i64,
+        )
     }
 
     fn part2(input: &str) -> Option<i64> {
-        None
+        let hail: Hail = input.parse().unwrap();
+        let (pos, _) = hail.throw_rock()?;
+        let sum = pos.iter().sum::<BigRational>();
+        // the rock has to start at integer coordinates
+        if !sum.is_integer() {
+            return None;
+        }
+        sum.to_integer().to_i64()
     }
 }
@@ -1,10 +1,175 @@
-use crate::Solution;
+use std::{collections::HashMap, str::FromStr};
+
+use itertools::Itertools;
+
+use crate::{Options, Solution};
 
 pub struct Day25;
 
+/// The wiring diagram as an undirected graph, with every component numbered
+#[derive(Debug)]
+struct Wiring {
+    names: Vec<String>,
+    edges: Vec<(usize, usize)>,
+}
+
+/// A split of the components into two groups
+#[derive(Debug)]
+struct Cut {
+    /// the wires that go between the groups
+    wires: Vec<(usize, usize)>,
+    sizes: (usize, usize),
+}
+
+/// Small splitmix64 generator, so that a seed always gives the same cut
+struct Rng(u64);
+
+/// Disjoint sets of components that have been contracted together
+struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+// == Parsing ==
+
+impl FromStr for Wiring {
+    type Err = &'static str;
+
+    /// Input like `jqt: rhn xhk nvd`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut ids: HashMap<&str, usize> = HashMap::new();
+        let mut names = vec![];
+        let mut id = |name| {
+            *ids.entry(name).or_insert_with(|| {
+                names.push(name.to_string());
+                names.len() - 1
+            })
+        };
+
+        let mut edges = vec![];
+        for line in s.lines() {
+            let (from, tos) = line.split_once(": ").ok_or("Missing ': '")?;
+            let from = id(from);
+            for to in tos.split_whitespace() {
+                edges.push((from, id(to)));
+            }
+        }
+        Ok(Wiring { names, edges })
+    }
+}
+
+// == Solution code ==
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, (self.next() % (i as u64 + 1)) as usize);
+        }
+    }
+}
+
+impl UnionFind {
+    fn new(n: usize) -> Self {
+        UnionFind {
+            parent: (0..n).collect(),
+            size: vec![1; n],
+        }
+    }
+
+    fn find(&mut self, x: usize) -> usize {
+        if self.parent[x] != x {
+            self.parent[x] = self.find(self.parent[x]);
+        }
+        self.parent[x]
+    }
+
+    /// Merge the sets holding `a` and `b`. Returns false if they were already merged.
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        let (big, small) = if self.size[a] >= self.size[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parent[small] = big;
+        self.size[big] += self.size[small];
+        true
+    }
+}
+
+impl Wiring {
+    /// One round of Karger's algorithm: contract random edges until only two groups of
+    /// components are left. Contracting the edges in a random order is the same thing as
+    /// building a spanning forest in that order, so we can do it with union-find.
+    fn contract(&self, rng: &mut Rng) -> Cut {
+        let mut order = self.edges.clone();
+        rng.shuffle(&mut order);
+
+        let mut groups = UnionFind::new(self.names.len());
+        let mut remaining = self.names.len();
+        for (a, b) in order {
+            if remaining == 2 {
+                break;
+            }
+            if groups.union(a, b) {
+                remaining -= 1;
+            }
+        }
+
+        let wires = self
+            .edges
+            .iter()
+            .filter(|(a, b)| groups.find(*a) != groups.find(*b))
+            .copied()
+            .collect_vec();
+        let root = groups.find(0);
+        let size = groups.size[root];
+        Cut {
+            wires,
+            sizes: (size, self.names.len() - size),
+        }
+    }
+
+    /// Repeat Karger's algorithm until it finds a cut of exactly `wires` edges.
+    /// Each round only finds the minimum cut with some probability, so give up after
+    /// `max_rounds`.
+    fn min_cut(&self, wires: usize, seed: u64, max_rounds: usize) -> Option<Cut> {
+        let mut rng = Rng(seed);
+        (0..max_rounds)
+            .map(|_| self.contract(&mut rng))
+            .find(|cut| cut.wires.len() == wires)
+    }
+}
+
 impl Solution for Day25 {
     fn part1(input: &str) -> Option<i64> {
-        None
+        Self::part1_with(input, &Options::default())
+    }
+
+    /// Pass `-o seed=N` to change the seed for the random contractions
+    fn part1_with(input: &str, opts: &Options) -> Option<i64> {
+        let wiring: Wiring = input.parse().unwrap();
+        let seed = opts.get("seed").unwrap_or(2023);
+        let cut = wiring.min_cut(3, seed, 100_000)?;
+        println!(
+            "Cut {}",
+            cut.wires
+                .iter()
+                .map(|(a, b)| format!("{}/{}", wiring.names[*a], wiring.names[*b]))
+                .join(", ")
+        );
+        Some((cut.sizes.0 * cut.sizes.1) as i64)
     }
 
     fn part2(input: &str) -> Option<i64> {
@@ -1,17 +1,31 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{collections::HashSet, str::FromStr};
 
+use color_print::cprint;
 use itertools::Itertools;
 
-use crate::Solution;
+use crate::{
+    grid::{Grid, Pos},
+    Options, Solution,
+};
 
 pub struct Day3;
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 struct Schematic {
-    /// maps coordinates to numbers
-    grid: HashMap<(i32, i32), (usize, i32)>,
+    /// for every cell covered by a number, the id of that number and its value
+    grid: Grid<Option<(usize, i32)>>,
     /// list of coordinates where symbols exist
-    symbols: Vec<(i32, i32, char)>,
+    symbols: Vec<(Pos, char)>,
+    /// every number, indexed by its id
+    numbers: Vec<Number>,
+}
+
+#[derive(Debug)]
+struct Number {
+    /// the position of the first digit
+    pos: Pos,
+    digits: usize,
+    value: i32,
 }
 
 #[derive(Clone, Debug)]
@@ -22,18 +36,81 @@ enum SchematicItem {
 }
 
 impl Schematic {
-    fn nums_adjacent(&self, x: i32, y: i32) -> impl Iterator<Item = i32> + '_ {
-        (-1..=1)
-            .cartesian_product(-1..=1)
-            .filter_map(move |(xoff, yoff)| self.grid.get(&(x + xoff, y + yoff)))
-            .unique_by(|(id, _)| id)
-            .map(|(_, val)| *val)
+    fn nums_adjacent(&self, pos: Pos) -> impl Iterator<Item = i32> + '_ {
+        self.grid
+            .neighbors8(pos)
+            .filter_map(|pos| self.grid[pos])
+            .unique_by(|(id, _)| *id)
+            .map(|(_, val)| val)
+    }
+
+    /// The ids of the numbers next to any symbol in `class`, each one only once
+    fn numbers_adjacent_to(&self, class: impl Fn(char) -> bool) -> Vec<usize> {
+        self.symbols
+            .iter()
+            .filter(|(_, c)| class(*c))
+            .flat_map(|(pos, _)| self.grid.neighbors8(*pos))
+            .filter_map(|pos| self.grid[pos])
+            .map(|(id, _)| id)
+            .unique()
+            .sorted()
+            .collect()
+    }
+
+    /// The symbols in `class` that have exactly `k` numbers next to them, with those numbers
+    fn symbols_with(&self, k: usize, class: impl Fn(char) -> bool) -> Vec<(Pos, Vec<i32>)> {
+        self.symbols
+            .iter()
+            .filter(|(_, c)| class(*c))
+            .map(|(pos, _)| (*pos, self.nums_adjacent(*pos).collect_vec()))
+            .filter(|(_, nums)| nums.len() == k)
+            .collect()
+    }
+
+    /// The ids of the numbers that aren't next to any symbol at all
+    fn lonely_numbers(&self) -> Vec<usize> {
+        let parts: HashSet<usize> = self.numbers_adjacent_to(|_| true).into_iter().collect();
+        (0..self.numbers.len())
+            .filter(|id| !parts.contains(id))
+            .collect()
+    }
+
+    /// Print the schematic, with the `counted` numbers in green, the symbols in
+    /// `highlighted` in yellow, and the numbers that don't touch any symbol dimmed
+    fn display(&self, counted: &[usize], highlighted: &[Pos]) {
+        let lonely = self.lonely_numbers();
+        let mut chars = self.grid.map(|_| '.');
+        for (pos, c) in &self.symbols {
+            chars[*pos] = *c;
+        }
+        for number in &self.numbers {
+            let Pos(x, y) = number.pos;
+            let digits = format!("{:0width$}", number.value, width = number.digits);
+            for (i, digit) in digits.chars().enumerate() {
+                chars[Pos(x + i, y)] = digit;
+            }
+        }
+
+        for (y, row) in chars.rows().enumerate() {
+            for (x, c) in row.iter().enumerate() {
+                let id = self.grid[Pos(x, y)].map(|(id, _)| id);
+                match id {
+                    Some(id) if counted.contains(&id) => cprint!("<green>{c}</>"),
+                    Some(id) if lonely.contains(&id) => cprint!("<dim>{c}</>"),
+                    Some(_) => print!("{c}"),
+                    None if highlighted.contains(&Pos(x, y)) => cprint!("<yellow,bold>{c}</>"),
+                    None if *c != '.' => cprint!("<red>{c}</>"),
+                    None => cprint!("<dim>{c}</>"),
+                }
+            }
+            println!();
+        }
     }
 }
 
 impl SchematicItem {
     fn is_symbol_or_space(c: char) -> bool {
-        c == '.' || !c.is_digit(10)
+        c == '.' || !c.is_ascii_digit()
     }
 }
 
@@ -53,20 +130,30 @@ impl FromStr for Schematic {
     type Err = &'static str;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut scheme = Schematic::default();
+        let chars: Grid<char> = s.parse()?;
+        let mut scheme = Schematic {
+            grid: Grid::new(chars.width(), chars.height(), None),
+            symbols: vec![],
+            numbers: vec![],
+        };
         let mut id = 0;
 
-        for (y, line) in s.lines().enumerate() {
-            line.chars().schematic().fold(0, |x, el| match el {
+        for (y, line) in chars.rows().enumerate() {
+            line.iter().copied().schematic().fold(0, |x, el| match el {
                 SchematicItem::Number(num, digits) => {
-                    for i in 0..digits {
-                        scheme.grid.insert((x + i, y as i32), (id, num));
+                    for i in 0..digits as usize {
+                        scheme.grid[Pos(x + i, y)] = Some((id, num));
                     }
+                    scheme.numbers.push(Number {
+                        pos: Pos(x, y),
+                        digits: digits as usize,
+                        value: num,
+                    });
                     id += 1;
-                    x + digits
+                    x + digits as usize
                 }
                 SchematicItem::Symbol(c) => {
-                    scheme.symbols.push((x, y as i32, c));
+                    scheme.symbols.push((Pos(x, y), c));
                     x + 1
                 }
                 SchematicItem::Space => x + 1,
@@ -145,29 +232,54 @@ impl<I: Iterator<Item = char>> SchematicIterAdapter for I {}
 
 impl Solution for Day3 {
     fn part1(input: &str) -> Option<i64> {
+        Self::part1_with(input, &Options::default())
+    }
+
+    fn part2(input: &str) -> Option<i64> {
+        Self::part2_with(input, &Options::default())
+    }
+
+    /// Pass `-o symbols=...` to only count numbers next to those symbols, and
+    /// `-o display` to show which numbers were counted
+    fn part1_with(input: &str, opts: &Options) -> Option<i64> {
         let scheme = input.parse::<Schematic>().unwrap();
+        let class = opts.get::<String>("symbols");
+        let in_class = |c: char| class.as_ref().is_none_or(|class| class.contains(c));
+        if opts.flag("display") {
+            scheme.display(&scheme.numbers_adjacent_to(in_class), &[]);
+        }
 
         Some(
             scheme
                 .symbols
                 .iter()
-                .map(|(x, y, _)| scheme.nums_adjacent(*x, *y))
-                .flatten()
+                .filter(|(_, c)| in_class(*c))
+                .flat_map(|(pos, _)| scheme.nums_adjacent(*pos))
                 .sum::<i32>() as i64,
         )
     }
 
-    fn part2(input: &str) -> Option<i64> {
+    /// Pass `-o gear=C` and `-o k=N` to look for symbols other than `*` with some other
+    /// number of neighbors, and `-o display` to show the gears that were found
+    fn part2_with(input: &str, opts: &Options) -> Option<i64> {
         let scheme = input.parse::<Schematic>().unwrap();
+        let gear = opts.get("gear").unwrap_or('*');
+        let k = opts.get("k").unwrap_or(2);
+        let gears = scheme.symbols_with(k, |c| c == gear);
+        if opts.flag("display") {
+            let ids = gears
+                .iter()
+                .flat_map(|(pos, _)| scheme.grid.neighbors8(*pos))
+                .filter_map(|pos| scheme.grid[pos])
+                .map(|(id, _)| id)
+                .collect_vec();
+            scheme.display(&ids, &gears.iter().map(|(pos, _)| *pos).collect_vec());
+        }
 
         Some(
-            scheme
-                .symbols
+            gears
                 .iter()
-                .filter(|(_, _, c)| c == &'*')
-                .map(|(x, y, _)| scheme.nums_adjacent(*x, *y).collect_vec())
-                .filter(|vals| vals.len() == 2)
-                .map(|vals| vals[0] * vals[1])
+                .map(|(_, nums)| nums.iter().product::<i32>())
                 .sum::<i32>() as i64,
         )
     }
@@ -1,15 +1,31 @@
-use std::{collections::VecDeque, str::FromStr};
+use std::{fmt::Display, str::FromStr};
 
 use itertools::Itertools;
+use num::{BigUint, One, ToPrimitive};
 
-use crate::Solution;
+use crate::{Options, Solution};
 
 pub struct Day4;
 
 #[derive(Debug)]
 struct Game {
     cards: Vec<Card>,
-    worklist: VecDeque<usize>,
+}
+
+/// What to do when a card wins copies of cards past the end of the table
+#[derive(Debug, Clone, Copy)]
+enum PastEnd {
+    /// only copy the cards that are there
+    Clamp,
+    /// give up on the whole game
+    Error,
+}
+
+/// A card that won copies of cards past the end of the table
+#[derive(Debug)]
+struct OffTheTable {
+    id: usize,
+    wins: usize,
 }
 
 impl FromStr for Game {
@@ -17,8 +33,30 @@ impl FromStr for Game {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let cards: Vec<_> = s.lines().map(|line| line.parse().unwrap()).collect();
-        let worklist: VecDeque<_> = (0..cards.len()).collect();
-        Ok(Game { cards, worklist })
+        Ok(Game { cards })
+    }
+}
+
+impl FromStr for PastEnd {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clamp" => Ok(PastEnd::Clamp),
+            "error" => Ok(PastEnd::Error),
+            _ => Err("Expected clamp or error"),
+        }
+    }
+}
+
+impl Display for OffTheTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Card {} wins copies of the next {} cards, which runs past the end of the table",
+            self.id + 1,
+            self.wins
+        )
     }
 }
 
@@ -44,14 +82,18 @@ impl FromStr for Card {
                     .expect("invalid id parsing"))
                     - 1,
                 lucky_numbers: lucky
-                    .trim()
                     .split_whitespace()
-                    .map(|num| num.parse().expect(&format!("{num}")))
+                    .map(|num| {
+                        num.parse()
+                            .unwrap_or_else(|_| panic!("invalid number {num}"))
+                    })
                     .collect_vec(),
                 drawn_numbers: drawn
-                    .trim()
                     .split_whitespace()
-                    .map(|num| num.parse().expect(&format!("{num}")))
+                    .map(|num| {
+                        num.parse()
+                            .unwrap_or_else(|_| panic!("invalid number {num}"))
+                    })
                     .collect_vec(),
             }),
             _ => Err("Malformed input"),
@@ -88,20 +130,25 @@ impl Game {
             .collect()
     }
 
-    fn explode(&mut self) -> usize {
-        let mut total = 0;
+    /// How many of each card we end up with. Cards only ever win copies of the cards
+    /// after them, so by the time we get to a card we already know how many copies of
+    /// it there are, and each of them wins the same cards.
+    fn copies(&self, past_end: PastEnd) -> Result<Vec<BigUint>, OffTheTable> {
         let cache = self.populate_cache();
-        while let Some(card_id) = self.worklist.pop_front() {
-            total += 1;
-            let card = &self.cards[card_id];
-            let wins = cache[card_id];
-            if wins > 0 {
-                for next_id in 0..wins {
-                    self.worklist.push_back(card.id + next_id + 1)
+        let mut copies = vec![BigUint::one(); self.cards.len()];
+        for (card_id, (card, &wins)) in self.cards.iter().zip(&cache).enumerate() {
+            let last = card_id + wins;
+            if last >= self.cards.len() {
+                if let PastEnd::Error = past_end {
+                    return Err(OffTheTable { id: card.id, wins });
                 }
             }
+            let n = copies[card_id].clone();
+            for won in copies.iter_mut().take(last + 1).skip(card_id + 1) {
+                *won += &n;
+            }
         }
-        total
+        Ok(copies)
     }
 }
 
@@ -112,7 +159,25 @@ impl Solution for Day4 {
     }
 
     fn part2(input: &str) -> Option<i64> {
-        let mut game: Game = input.parse().unwrap();
-        Some(game.explode() as i64)
+        Self::part2_with(input, &Options::default())
+    }
+
+    /// Pass `-o past_end=error` to reject cards that win copies of cards past the end
+    /// of the table, instead of just copying the ones that are there
+    fn part2_with(input: &str, opts: &Options) -> Option<i64> {
+        let game: Game = input.parse().unwrap();
+        let copies = match game.copies(opts.get("past_end").unwrap_or(PastEnd::Clamp)) {
+            Ok(copies) => copies,
+            Err(err) => {
+                eprintln!("{err}");
+                return None;
+            }
+        };
+        let total = copies.into_iter().sum::<BigUint>();
+        let answer = total.to_i64();
+        if answer.is_none() {
+            eprintln!("There are {total} cards, which doesn't fit in an i64");
+        }
+        answer
     }
 }
@@ -1,17 +1,8 @@
-use std::{collections::VecDeque, str::FromStr};
+use std::{collections::VecDeque, fmt::Display, str::FromStr};
 
-use indicatif::{
-    ParallelProgressIterator, ProgressBar, ProgressFinish, ProgressIterator, ProgressStyle,
-};
 use itertools::Itertools;
-use rayon::{
-    iter::{IntoParallelRefIterator, IntoParallelRefMutIterator, ParallelIterator},
-    prelude::ParallelBridge,
-    slice::ParallelSlice,
-    ThreadPoolBuilder,
-};
 
-use crate::Solution;
+use crate::{Options, Solution};
 
 pub struct Day5;
 
@@ -27,6 +18,9 @@ struct Map {
     ranges: Vec<Range>,
 }
 
+/// A half-open interval of values
+type Interval = std::ops::Range<usize>;
+
 #[derive(Debug)]
 struct Range {
     dest_start: usize,
@@ -34,6 +28,24 @@ struct Range {
     length: usize,
 }
 
+/// The whole almanac squashed into one function from seed to location. Every piece
+/// maps its source interval onto its destination interval of the same length, and
+/// the sources cover every value without overlapping.
+#[derive(Debug)]
+struct Piecewise {
+    pieces: Vec<(Interval, Interval)>,
+}
+
+/// Two ranges in the same map that both claim some of the same source values.
+/// `first` and `second` are indices into the map's ranges.
+#[derive(Debug)]
+struct Overlap {
+    map: String,
+    first: usize,
+    second: usize,
+    values: Interval,
+}
+
 // ==== Parsing Code ====
 
 impl FromStr for Input {
@@ -95,20 +107,28 @@ impl FromStr for Range {
 impl Almanac {
     fn translate_vec(&self, input: &[usize]) -> Vec<usize> {
         let mut values = input.to_vec();
-        for (name, map) in &self.maps {
-            values
-                .par_iter_mut()
-                .progress_with_style(
-                    ProgressStyle::with_template("{msg} [{elapsed_precise}] [{bar:.cyan/blue}]")
-                        .unwrap()
-                        .progress_chars("#>-"),
-                )
-                .with_message(name.to_string())
-                .with_finish(ProgressFinish::AndLeave)
-                .for_each(|seed| *seed = map.translate(*seed));
+        for (_, map) in &self.maps {
+            for seed in values.iter_mut() {
+                *seed = map.translate(*seed);
+            }
         }
         values
     }
+
+    /// Translate a set of intervals through every map, merging them back together after
+    /// each step so that the set stays small
+    fn translate_intervals(&self, input: Vec<Interval>) -> Vec<Interval> {
+        self.maps
+            .iter()
+            .fold(normalize(input), |intervals, (_, map)| {
+                normalize(
+                    intervals
+                        .into_iter()
+                        .flat_map(|interval| map.translate_interval(interval))
+                        .collect(),
+                )
+            })
+    }
 }
 
 impl Map {
@@ -118,11 +138,63 @@ impl Map {
                 return range.translate(input);
             }
         }
-        return input;
+        input
+    }
+
+    /// Translate a whole interval, which gets split up wherever it crosses the edge of
+    /// a range. Like `translate`, the first range that covers a value wins, and values
+    /// that no range covers stay as they are.
+    fn translate_interval(&self, input: Interval) -> Vec<Interval> {
+        self.split_interval(input)
+            .into_iter()
+            .map(|(_, to)| to)
+            .collect()
+    }
+
+    /// Split up `input` like `translate_interval`, but keep each part of the input
+    /// together with the interval it translates to
+    fn split_interval(&self, input: Interval) -> Vec<(Interval, Interval)> {
+        let mut translated = vec![];
+        let mut untouched = vec![input];
+        for range in &self.ranges {
+            untouched = untouched
+                .into_iter()
+                .flat_map(|interval| {
+                    let (inside, outside) = range.split(interval);
+                    translated.extend(
+                        inside.map(|inside| (inside.clone(), range.translate_interval(inside))),
+                    );
+                    outside
+                })
+                .collect();
+        }
+        translated.extend(
+            untouched
+                .into_iter()
+                .map(|interval| (interval.clone(), interval)),
+        );
+        translated
+    }
+
+    /// Every pair of ranges whose sources overlap, by their index in the map
+    fn overlaps(&self) -> impl Iterator<Item = (usize, usize, Interval)> + '_ {
+        self.ranges
+            .iter()
+            .enumerate()
+            .tuple_combinations()
+            .filter_map(|((i, r1), (j, r2))| {
+                let (src1, src2) = (r1.src(), r2.src());
+                let both = src1.start.max(src2.start)..src1.end.min(src2.end);
+                (!both.is_empty()).then_some((i, j, both))
+            })
     }
 }
 
 impl Range {
+    fn src(&self) -> Interval {
+        self.src_start..self.src_start + self.length
+    }
+
     fn contains(&self, input: usize) -> bool {
         self.src_start <= input && input < self.src_start + self.length
     }
@@ -134,11 +206,162 @@ impl Range {
             input
         }
     }
+
+    /// Translate an interval that lies completely inside the source range
+    fn translate_interval(&self, input: Interval) -> Interval {
+        self.translate(input.start)..self.translate(input.start) + input.len()
+    }
+
+    /// Split `input` into the part inside the source range, and the (up to two) parts
+    /// on either side of it
+    fn split(&self, input: Interval) -> (Option<Interval>, Vec<Interval>) {
+        let src = self.src();
+        let inside = input.start.max(src.start)..input.end.min(src.end);
+        let before = input.start..input.end.min(src.start);
+        let after = input.start.max(src.end)..input.end;
+        (
+            (!inside.is_empty()).then_some(inside),
+            [before, after]
+                .into_iter()
+                .filter(|part| !part.is_empty())
+                .collect(),
+        )
+    }
+}
+
+/// Sort the intervals, and merge the ones that overlap or touch
+fn normalize(mut intervals: Vec<Interval>) -> Vec<Interval> {
+    intervals.retain(|interval| !interval.is_empty());
+    intervals.sort_by_key(|interval| interval.start);
+    intervals.into_iter().fold(vec![], |mut merged, interval| {
+        match merged.last_mut() {
+            Some(last) if interval.start <= last.end => last.end = last.end.max(interval.end),
+            _ => merged.push(interval),
+        }
+        merged
+    })
+}
+
+impl Almanac {
+    /// Compose all of the maps, in order, into a single function. We start from the
+    /// identity on every value, and push each piece through the next map, which splits
+    /// it wherever it crosses the edge of a range.
+    fn compose(&self) -> Piecewise {
+        let identity = vec![(0..usize::MAX, 0..usize::MAX)];
+        let pieces = self.maps.iter().fold(identity, |pieces, (_, map)| {
+            pieces
+                .into_iter()
+                .flat_map(|(seeds, current)| {
+                    map.split_interval(current.clone())
+                        .into_iter()
+                        .map(move |(from, to)| {
+                            let start = seeds.start + (from.start - current.start);
+                            (start..start + from.len(), to)
+                        })
+                })
+                .collect()
+        });
+
+        // glue back together the neighboring pieces that ended up being translated the same way
+        let pieces = pieces
+            .into_iter()
+            .sorted_by_key(|(seeds, _)| seeds.start)
+            .fold(
+                vec![],
+                |mut merged: Vec<(Interval, Interval)>, (seeds, to)| {
+                    match merged.last_mut() {
+                        Some((last_seeds, last_to))
+                            if last_seeds.end == seeds.start && last_to.end == to.start =>
+                        {
+                            last_seeds.end = seeds.end;
+                            last_to.end = to.end;
+                        }
+                        _ => merged.push((seeds, to)),
+                    }
+                    merged
+                },
+            );
+        Piecewise { pieces }
+    }
+
+    fn overlaps(&self) -> Vec<Overlap> {
+        self.maps
+            .iter()
+            .flat_map(|(name, map)| {
+                map.overlaps().map(|(first, second, values)| Overlap {
+                    map: name.to_string(),
+                    first,
+                    second,
+                    values,
+                })
+            })
+            .collect()
+    }
+}
+
+impl Piecewise {
+    /// Every seed interval that maps into `target`
+    fn preimage(&self, target: Interval) -> Vec<Interval> {
+        normalize(
+            self.pieces
+                .iter()
+                .filter_map(|(seeds, to)| {
+                    let hit = target.start.max(to.start)..target.end.min(to.end);
+                    (!hit.is_empty()).then(|| {
+                        let start = seeds.start + (hit.start - to.start);
+                        start..start + hit.len()
+                    })
+                })
+                .collect(),
+        )
+    }
+}
+
+impl Display for Piecewise {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:>45} | locations", "seeds")?;
+        writeln!(f, "{:->45}-+-{:-<45}", "", "")?;
+        for (seeds, to) in &self.pieces {
+            let seeds = format!("{}..{}", seeds.start, seeds.end);
+            writeln!(f, "{seeds:>45} | {}..{}", to.start, to.end)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Overlap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Lines {} and {} of the {} map both cover {}..{}, only line {} is used for those",
+            self.first + 1,
+            self.second + 1,
+            self.map,
+            self.values.start,
+            self.values.end,
+            self.first + 1
+        )
+    }
 }
 
 impl Solution for Day5 {
     fn part1(input: &str) -> Option<i64> {
-        let Input(seeds, mut almanac) = input.parse().unwrap();
+        Self::part1_with(input, &Options::default())
+    }
+
+    fn part2(input: &str) -> Option<i64> {
+        Self::part2_with(input, &Options::default())
+    }
+
+    /// Pass `-o table` to print the whole almanac as one function from seed to location
+    fn part1_with(input: &str, opts: &Options) -> Option<i64> {
+        let Input(seeds, almanac) = input.parse().unwrap();
+        for overlap in almanac.overlaps() {
+            eprintln!("{overlap}");
+        }
+        if opts.flag("table") {
+            print!("{}", almanac.compose());
+        }
         almanac
             .translate_vec(&seeds)
             .iter()
@@ -147,24 +370,127 @@ impl Solution for Day5 {
             .map(|x| x as i64)
     }
 
-    fn part2(input: &str) -> Option<i64> {
-        println!("Starting part 2");
-        let Input(seeds, mut almanac) = input.parse().unwrap();
+    /// Pass `-o location_min=N -o location_max=M` to print the seeds that end up at those
+    /// locations (both ends included)
+    fn part2_with(input: &str, opts: &Options) -> Option<i64> {
+        let Input(seeds, almanac) = input.parse().unwrap();
+        if let (Some(min), Some(max)) =
+            (opts.get("location_min"), opts.get::<usize>("location_max"))
+        {
+            println!("Seeds that end up at locations {min}..={max}:");
+            // intervals are half open, so nothing can end past usize::MAX anyway
+            for seeds in almanac.compose().preimage(min..max.saturating_add(1)) {
+                println!("  {}..{}", seeds.start, seeds.end);
+            }
+        }
+        let ranges = seeds
+            .iter()
+            .tuples()
+            .map(|(start, length)| *start..start + length)
+            .collect();
+        almanac
+            .translate_intervals(ranges)
+            .first()
+            .map(|interval| interval.start as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "seeds: 79 14 55 13
+
+seed-to-soil map:
+50 98 2
+52 50 48
 
-        ThreadPoolBuilder::new().num_threads(2).build_global();
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
 
-        seeds
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
+
+water-to-light map:
+88 18 7
+18 25 70
+
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+
+temperature-to-humidity map:
+0 69 1
+1 0 69
+
+humidity-to-location map:
+60 56 37
+56 93 4";
+
+    fn almanac() -> Almanac {
+        let Input(_, almanac) = EXAMPLE.parse().unwrap();
+        almanac
+    }
+
+    fn location(piecewise: &Piecewise, seed: usize) -> usize {
+        let (seeds, to) = piecewise
+            .pieces
             .iter()
-            .tuples()
-            .filter_map(|(start, length)| {
-                println!("Starting {start} {length}");
-                let range = (*start..(start + length));
-                almanac
-                    .translate_vec(&range.collect_vec())
-                    .into_iter()
-                    .min()
-            })
-            .min()
-            .map(|x| x as i64)
+            .find(|(seeds, _)| seeds.contains(&seed))
+            .unwrap();
+        to.start + (seed - seeds.start)
+    }
+
+    #[test]
+    fn example() {
+        assert_eq!(Day5::part1(EXAMPLE), Some(35));
+        assert_eq!(Day5::part2(EXAMPLE), Some(46));
+    }
+
+    #[test]
+    fn compose_matches_translate() {
+        let almanac = almanac();
+        let piecewise = almanac.compose();
+        // the pieces cover every seed exactly once
+        assert_eq!(piecewise.pieces[0].0.start, 0);
+        assert_eq!(piecewise.pieces.last().unwrap().0.end, usize::MAX);
+        for ((a, _), (b, _)) in piecewise.pieces.iter().tuple_windows() {
+            assert_eq!(a.end, b.start);
+        }
+        for seed in 0..200 {
+            assert_eq!(
+                location(&piecewise, seed),
+                almanac.translate_vec(&[seed])[0]
+            );
+        }
+    }
+
+    #[test]
+    fn preimage_of_lowest_location() {
+        let almanac = almanac();
+        let piecewise = almanac.compose();
+        let seeds = piecewise.preimage(40..50);
+        assert!(seeds.iter().any(|seeds| seeds.contains(&82)));
+        for seed in 0..200 {
+            let location = almanac.translate_vec(&[seed])[0];
+            assert_eq!(
+                seeds.iter().any(|seeds| seeds.contains(&seed)),
+                (40..50).contains(&location),
+                "seed {seed} goes to {location}"
+            );
+        }
+    }
+
+    #[test]
+    fn preimage_near_the_top() {
+        // every map leaves values this big alone
+        let top = usize::MAX - 5..usize::MAX;
+        assert_eq!(almanac().compose().preimage(top.clone()), vec![top]);
     }
 }
@@ -1,31 +1,12 @@
 use std::str::FromStr;
 
 use itertools::Itertools;
+use num::{BigUint, ToPrimitive, Zero};
 
 use crate::Solution;
 
 pub struct Day6;
 
-struct Quadratic {
-    a: f64,
-    b: f64,
-    c: f64,
-}
-
-impl Quadratic {
-    fn new(a: f64, b: f64, c: f64) -> Self {
-        Quadratic { a, b, c }
-    }
-
-    fn solve(&self) -> (f64, f64) {
-        let radical = f64::sqrt(self.b.powi(2) - (4. * self.a * self.c));
-        (
-            (-self.b + radical) / (2. * self.a),
-            (-self.b - radical) / (2. * self.a),
-        )
-    }
-}
-
 /// Wrapper type to hold part 1 races
 #[derive(Debug)]
 struct Races(Vec<Race>);
@@ -34,10 +15,11 @@ struct Races(Vec<Race>);
 #[derive(Debug)]
 struct LongRace(Race);
 
+/// The numbers can get as long as you like, since part 2 glues them together
 #[derive(Debug)]
 struct Race {
-    total_time: usize,
-    best_distance: usize,
+    total_time: BigUint,
+    best_distance: BigUint,
 }
 
 // == parsing ==
@@ -50,16 +32,15 @@ impl FromStr for Races {
                 .map(|line| {
                     line.split_whitespace()
                         .skip(1)
-                        .map(|n| n.parse::<usize>().unwrap())
+                        .map(|n| n.parse::<BigUint>().unwrap())
                 })
                 .tuples()
-                .map(|(time, distance)| {
+                .flat_map(|(time, distance)| {
                     time.zip(distance).map(|(total_time, best_distance)| Race {
                         total_time,
                         best_distance,
                     })
                 })
-                .flatten()
                 .collect_vec(),
         ))
     }
@@ -75,7 +56,7 @@ impl FromStr for LongRace {
                     line.split_whitespace()
                         .skip(1)
                         .join("")
-                        .parse::<usize>()
+                        .parse::<BigUint>()
                         .unwrap()
                 })
                 .tuples()
@@ -90,30 +71,59 @@ impl FromStr for LongRace {
 }
 
 impl Race {
-    fn winning_range(&self) -> (usize, usize) {
-        let (sol1, sol2) =
-            Quadratic::new(-1., self.total_time as f64, -(self.best_distance as f64)).solve();
+    /// Does holding the button for `hold` beat the best distance?
+    fn wins(&self, hold: &BigUint) -> bool {
+        hold <= &self.total_time && hold * (&self.total_time - hold) > self.best_distance
+    }
+
+    /// The number of ways to win.
+    ///
+    /// Holding for `h` goes `h * (t - h)`, so we win strictly between the roots of
+    /// `h² - t h + d = 0`, which are `(t ± √(t² - 4d)) / 2`. We find the lower root
+    /// with an exact integer square root, which can only be off by one from the first
+    /// winning hold, and check the boundary directly to fix that up. The winning holds
+    /// are symmetric around `t / 2`, so the upper end is `t - lower`.
+    fn ways_to_win(&self) -> BigUint {
+        let t = &self.total_time;
+        let square = t * t;
+        let four_d = &self.best_distance * 4u32;
+        if square < four_d {
+            return BigUint::zero();
+        }
+        let root = (square - four_d).sqrt();
+
+        let half = t / 2u32;
+        let mut lower = (t - root) / 2u32;
+        while lower > BigUint::zero() && self.wins(&(&lower - 1u32)) {
+            lower -= 1u32;
+        }
+        while lower <= half && !self.wins(&lower) {
+            lower += 1u32;
+        }
+        if lower > half {
+            return BigUint::zero();
+        }
+        t - &lower * 2u32 + 1u32
+    }
+}
 
-        // find the nearest integer solution
-        ((sol1 + 1.0).floor() as usize, (sol2 - 1.0).ceil() as usize)
+/// The answer, or a note about it if it's too big to give back
+fn answer(n: BigUint) -> Option<i64> {
+    let answer = n.to_i64();
+    if answer.is_none() {
+        eprintln!("The answer is {n}, which doesn't fit in an i64");
     }
+    answer
 }
 
 impl Solution for Day6 {
     fn part1(input: &str) -> Option<i64> {
         let Races(races) = input.parse().unwrap();
-        Some(
-            races
-                .iter()
-                .map(|r| r.winning_range())
-                .map(|(l, u)| u - l + 1)
-                .product::<usize>() as i64,
-        )
+        answer(races.iter().map(|r| r.ways_to_win()).product())
     }
 
     fn part2(input: &str) -> Option<i64> {
         let LongRace(race) = input.parse().unwrap();
-        let (l, u) = race.winning_range();
-        Some((u - l + 1) as i64)
+        answer(race.ways_to_win())
     }
 }
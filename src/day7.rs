@@ -1,116 +1,58 @@
-use std::{cmp::Ordering, fmt::Display, str::FromStr};
+use std::{fmt::Display, str::FromStr};
 
 use itertools::Itertools;
 
-use crate::Solution;
+use crate::{Options, Solution};
 
 pub struct Day7;
 
 #[derive(Debug)]
-struct Game(Vec<(Hand, usize)>);
+struct Game(Vec<(String, usize)>);
 
-#[derive(Debug, PartialEq, Eq)]
-struct Hand {
-    cards: [Card; 5],
-}
-
-/// Represents all cards as numbers from 2-14
-/// 2-9 just represent normal number cards.
-/// 10 -> T
-/// 11 -> J
-/// 12 -> Q
-/// 13 -> K
-/// 14 -> A
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
-struct Card(usize);
-
-#[derive(Clone, Debug, PartialEq, Eq)]
-enum Type {
-    FiveKind,
-    FourKind,
-    FullHouse,
-    ThreeKind,
-    TwoPair,
-    OnePair,
-    HighCard,
+/// Everything that decides how hands rank against each other
+#[derive(Debug)]
+struct Rules {
+    /// every card that can appear, from weakest to strongest
+    order: Vec<char>,
+    /// cards that stand in for whatever card makes the hand strongest. They still
+    /// use their place in `order` when breaking ties.
+    wildcards: Vec<char>,
+    hand_size: usize,
 }
 
-impl Type {
-    fn disriminant_id(&self) -> usize {
-        match self {
-            Type::FiveKind => 6,
-            Type::FourKind => 5,
-            Type::FullHouse => 4,
-            Type::ThreeKind => 3,
-            Type::TwoPair => 2,
-            Type::OnePair => 1,
-            Type::HighCard => 0,
-        }
-    }
-
-    /// Use 1 joker to upgrade your type
-    fn upgrade(&self, n_jokers: usize) -> Type {
-        match (self, n_jokers) {
-            // no jokers
-            (_, 0) => self.clone(),
-
-            (Type::FiveKind, 5) => Type::FiveKind,
-            (Type::FiveKind, _) => unreachable!(),
-
-            // 4, 1
-            (Type::FourKind, 4) | (Type::FourKind, 1) => Type::FiveKind,
-            (Type::FourKind, _) => unreachable!(),
-
-            // 3, 2
-            (Type::FullHouse, 3) | (Type::FullHouse, 2) => Type::FiveKind,
-            (Type::FullHouse, _) => unreachable!(),
-
-            // 3, 1, 1
-            (Type::ThreeKind, 3) | (Type::ThreeKind, 1) => Type::FourKind,
-            (Type::ThreeKind, _) => unreachable!(),
-
-            // 2, 2, 1
-            (Type::TwoPair, 2) => Type::FourKind,
-            (Type::TwoPair, 1) => Type::FullHouse,
-            (Type::TwoPair, _) => unreachable!(),
+/// The sizes of the groups of matching cards in a hand, largest first, so `[3, 2]`
+/// is a full house. Comparing these in order ranks the hands the same way as the
+/// usual list of types, and it works for any hand size.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Type(Vec<usize>);
 
-            // 2, 1, 1, 1
-            (Type::OnePair, 2) | (Type::OnePair, 1) => Type::ThreeKind,
-            (Type::OnePair, _) => unreachable!(),
-
-            // 1, 1, 1, 1, 1
-            (Type::HighCard, 1) => Type::OnePair,
-            (Type::HighCard, _) => unreachable!(),
-        }
-    }
-}
-
-impl PartialOrd for Type {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.disriminant_id().partial_cmp(&other.disriminant_id())
-    }
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Hand {
+    /// the type first, then the strength of every card in order to break ties
+    key: (Type, Vec<usize>),
+    cards: String,
 }
 
 // Display code
 
-impl Display for Hand {
+impl Display for Type {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let [a, b, c, d, e] = &self.cards;
-        write!(f, "{a}{b}{c}{d}{e}")
+        match self.0.as_slice() {
+            [5] => write!(f, "five of a kind"),
+            [4, 1] => write!(f, "four of a kind"),
+            [3, 2] => write!(f, "full house"),
+            [3, 1, 1] => write!(f, "three of a kind"),
+            [2, 2, 1] => write!(f, "two pair"),
+            [2, 1, 1, 1] => write!(f, "one pair"),
+            [1, 1, 1, 1, 1] => write!(f, "high card"),
+            groups => write!(f, "groups of {}", groups.iter().join("+")),
+        }
     }
 }
 
-impl Display for Card {
+impl Display for Hand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Card(x) if x < &10 => write!(f, "{x}"),
-            Card(10) => write!(f, "T"),
-            Card(11) => write!(f, "J"),
-            Card(12) => write!(f, "Q"),
-            Card(13) => write!(f, "K"),
-            Card(14) => write!(f, "A"),
-            _ => Err(std::fmt::Error),
-        }
+        write!(f, "{} ({})", self.cards, self.key.0)
     }
 }
 
@@ -119,124 +61,123 @@ impl FromStr for Game {
     type Err = &'static str;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Game(
-            s.lines()
-                .flat_map(|line| {
-                    line.split_whitespace()
-                        .tuples()
-                        .map(|(hand, bid)| (hand.parse().unwrap(), bid.parse().unwrap()))
-                        .collect_vec()
-                })
-                .collect_vec(),
-        ))
+        s.lines()
+            .map(|line| {
+                let (hand, bid) = line.split_whitespace().collect_tuple().ok_or("Bad line")?;
+                Ok((hand.to_string(), bid.parse().map_err(|_| "Bad bid")?))
+            })
+            .collect::<Result<_, _>>()
+            .map(Game)
     }
 }
 
-impl FromStr for Hand {
-    type Err = &'static str;
+// == Solution code ==
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.chars().collect_vec().as_slice() {
-            [a, b, c, d, e] => Ok(Hand {
-                cards: [
-                    (*a).into(),
-                    (*b).into(),
-                    (*c).into(),
-                    (*d).into(),
-                    (*e).into(),
-                ],
-            }),
-            _ => Err("Wrong sized hand"),
+impl Rules {
+    fn new(order: &str, wildcards: &str, hand_size: usize) -> Self {
+        Rules {
+            order: order.chars().collect(),
+            wildcards: wildcards.chars().collect(),
+            hand_size,
         }
     }
-}
 
-impl From<char> for Card {
-    fn from(value: char) -> Self {
-        match value {
-            c if c.is_numeric() => Card(c.to_digit(10).unwrap() as usize),
-            'T' => Card(10),
-            'J' => Card(11),
-            'Q' => Card(12),
-            'K' => Card(13),
-            'A' => Card(14),
-            _ => unreachable!(),
-        }
+    /// The rules from part 1, or part 2 if `jokers` is set, with any of them
+    /// swapped out by `-o order=...`, `-o wild=...` or `-o size=N`
+    fn from_options(jokers: bool, opts: &Options) -> Self {
+        let (order, wildcards) = if jokers {
+            ("J23456789TQKA", "J")
+        } else {
+            ("23456789TJQKA", "")
+        };
+        Rules::new(
+            &opts.get::<String>("order").unwrap_or(order.to_string()),
+            &opts.get::<String>("wild").unwrap_or(wildcards.to_string()),
+            opts.get("size").unwrap_or(5),
+        )
     }
-}
-
-// == Solution code ==
 
-impl Hand {
-    fn type_of(&self) -> Type {
-        let mut bins = [0; 15];
-        for Card(c) in &self.cards {
-            bins[*c] += 1;
+    fn hand(&self, cards: &str) -> Result<Hand, &'static str> {
+        if cards.chars().count() != self.hand_size {
+            return Err("Wrong sized hand");
         }
-        let n_jokers = bins[1];
-        let key = bins
-            .into_iter()
-            .filter(|x| *x > 0)
+        let strengths = cards
+            .chars()
+            .map(|c| self.order.iter().position(|o| *o == c))
+            .collect::<Option<Vec<_>>>()
+            .ok_or("Unknown card")?;
+
+        // the wildcards all join the biggest group, which always makes the best type
+        let wild = cards.chars().filter(|c| self.wildcards.contains(c)).count();
+        let mut groups = cards
+            .chars()
+            .filter(|c| !self.wildcards.contains(c))
+            .counts()
+            .into_values()
             .sorted()
             .rev()
             .collect_vec();
-        let typ = match key.as_slice() {
-            [5] => Type::FiveKind,
-            [4, 1] => Type::FourKind,
-            [3, 2] => Type::FullHouse,
-            [3, 1, 1] => Type::ThreeKind,
-            [2, 2, 1] => Type::TwoPair,
-            [2, 1, 1, 1] => Type::OnePair,
-            [1, 1, 1, 1, 1] => Type::HighCard,
-            _ => unreachable!(),
-        };
-        typ.upgrade(n_jokers)
+        match groups.first_mut() {
+            Some(biggest) => *biggest += wild,
+            None => groups.push(wild),
+        }
+
+        Ok(Hand {
+            key: (Type(groups), strengths),
+            cards: cards.to_string(),
+        })
+    }
+
+    /// Rank every hand, and add up the bids multiplied by the ranks
+    fn winnings(&self, game: &Game, show: bool) -> Result<usize, &'static str> {
+        let hands = game
+            .0
+            .iter()
+            .map(|(cards, bid)| Ok((self.hand(cards)?, *bid)))
+            .collect::<Result<Vec<_>, &'static str>>()?;
+        Ok(hands
+            .into_iter()
+            .sorted()
+            .enumerate()
+            .inspect(|(rank, (hand, bid))| {
+                if show {
+                    println!("{:>5}: {hand} bids {bid}", rank + 1);
+                }
+            })
+            .map(|(rank, (_, bid))| (rank + 1) * bid)
+            .sum())
     }
 }
 
-impl PartialOrd for Hand {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        match self.type_of().partial_cmp(&other.type_of()) {
-            Some(Ordering::Equal) => Some(self.cards.iter().zip(other.cards.iter()).fold(
-                Ordering::Equal,
-                |acc, (c1, c2)| match acc {
-                    Ordering::Equal => c1.cmp(c2),
-                    _ => acc,
-                },
-            )),
-            Some(x) => Some(x),
-            None => None,
+impl Day7 {
+    fn solve(input: &str, jokers: bool, opts: &Options) -> Option<i64> {
+        let game: Game = input.parse().unwrap();
+        match Rules::from_options(jokers, opts).winnings(&game, opts.flag("show")) {
+            Ok(winnings) => Some(winnings as i64),
+            Err(err) => {
+                eprintln!("{err}");
+                None
+            }
         }
     }
 }
 
 impl Solution for Day7 {
     fn part1(input: &str) -> Option<i64> {
-        let Game(games) = input.parse().unwrap();
-        Some(
-            games
-                .iter()
-                .sorted_by(|(h1, _), (h2, _)| h1.partial_cmp(h2).unwrap())
-                .enumerate()
-                .map(|(rank, (h, bid))| (rank + 1) * bid)
-                .sum::<usize>() as i64,
-        )
+        Self::part1_with(input, &Options::default())
     }
 
     fn part2(input: &str) -> Option<i64> {
-        println!("");
-        println!("== part 2 ==");
-        // we are going to replace all instances of J with 1 which
-        // will represent the joker. this will automatically handle
-        // the secondary ranking
-        let Game(games) = input.replace('J', "1").parse().unwrap();
-        Some(
-            games
-                .iter()
-                .sorted_by(|(h1, _), (h2, _)| h1.partial_cmp(h2).unwrap())
-                .enumerate()
-                .map(|(rank, (h, bid))| (rank + 1) * bid)
-                .sum::<usize>() as i64,
-        )
+        Self::part2_with(input, &Options::default())
+    }
+
+    /// Pass `-o order=...`, `-o wild=...` and `-o size=N` to play with different rules,
+    /// and `-o show` to print the hands in order
+    fn part1_with(input: &str, opts: &Options) -> Option<i64> {
+        Self::solve(input, false, opts)
+    }
+
+    fn part2_with(input: &str, opts: &Options) -> Option<i64> {
+        Self::solve(input, true, opts)
     }
 }
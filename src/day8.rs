@@ -2,7 +2,7 @@ use std::{collections::HashMap, str::FromStr};
 
 use itertools::Itertools;
 
-use crate::Solution;
+use crate::{math::crt, Solution};
 
 pub struct Day8;
 
@@ -22,6 +22,17 @@ struct Node {
     right: String,
 }
 
+/// Everything about when a ghost stands on a node ending with Z. Its walk only depends
+/// on the node and the position in the instructions, so after `tail` steps it is stuck
+/// in a loop that repeats every `cycle` steps.
+#[derive(Debug)]
+struct Ghost {
+    tail: usize,
+    cycle: usize,
+    /// every step count below `tail + cycle` where the ghost is on a Z node
+    hits: Vec<usize>,
+}
+
 // == Parsing ==
 impl FromStr for Input {
     type Err = &'static str;
@@ -80,19 +91,76 @@ impl Node {
         }
     }
 
-    fn frequency(&self, map: &HashMap<String, Node>, insts: &[Inst]) -> usize {
-        let mut freq = 0;
+    /// Walk from this node until we are back in a state that we've seen before
+    fn ghost(&self, map: &HashMap<String, Node>, insts: &[Inst]) -> Ghost {
+        let mut seen: HashMap<(&str, usize), usize> = HashMap::new();
+        let mut hits = vec![];
         let mut node_ref = self;
-        while !node_ref.name.ends_with("Z") {
-            freq += 1;
-            for inst in insts {
-                node_ref = node_ref.step(map, inst);
+        for steps in 0.. {
+            let state = (node_ref.name.as_str(), steps % insts.len());
+            if let Some(&tail) = seen.get(&state) {
+                return Ghost {
+                    tail,
+                    cycle: steps - tail,
+                    hits,
+                };
+            }
+            seen.insert(state, steps);
+            if node_ref.name.ends_with('Z') {
+                hits.push(steps);
             }
+            node_ref = node_ref.step(map, &insts[state.1]);
         }
-        freq
+        unreachable!()
+    }
+}
+
+impl Ghost {
+    fn on_z_at(&self, steps: usize) -> bool {
+        let steps = if steps < self.tail {
+            steps
+        } else {
+            self.tail + (steps - self.tail) % self.cycle
+        };
+        self.hits.contains(&steps)
+    }
+
+    /// The step counts in the loop where the ghost is on a Z node
+    fn looped_hits(&self) -> impl Iterator<Item = usize> + '_ {
+        self.hits.iter().copied().filter(|hit| *hit >= self.tail)
     }
 }
 
+/// The first step count where every ghost is on a Z node at the same time.
+///
+/// Before the longest tail is over, we just check every step. After that, all of
+/// the ghosts are in their loops, so each choice of one hit per ghost is a system of
+/// congruences, and the earliest answer is the smallest solution over all choices.
+fn first_alignment(ghosts: &[Ghost]) -> Option<i128> {
+    let longest_tail = ghosts.iter().map(|g| g.tail).max()?;
+    if let Some(steps) = (0..longest_tail).find(|steps| ghosts.iter().all(|g| g.on_z_at(*steps))) {
+        return Some(steps as i128);
+    }
+
+    // combine one ghost at a time, so that choices agreeing on a residue collapse together
+    ghosts
+        .iter()
+        .fold(vec![(0, 1)], |partial, ghost| {
+            partial
+                .iter()
+                .cartesian_product(ghost.looped_hits().collect_vec())
+                .filter_map(|(&(r, m), hit)| crt([(r, m), (hit as i128, ghost.cycle as i128)]))
+                .unique()
+                .collect_vec()
+        })
+        .into_iter()
+        .map(|(r, m)| {
+            // the smallest solution that isn't before the longest tail
+            let tail = longest_tail as i128;
+            r + (tail - r + m - 1) / m * m
+        })
+        .min()
+}
 impl Solution for Day8 {
     fn part1(input: &str) -> Option<i64> {
         let Input(insts, nodes) = input.parse().unwrap();
@@ -110,21 +178,18 @@ impl Solution for Day8 {
     fn part2(input: &str) -> Option<i64> {
         let Input(insts, map) = input.parse().unwrap();
 
-        let mut insts_iter = insts.iter().cycle();
-
-        // this works by finding the frequency of each node that ends with A
-        // seperately. The freqency is the number of full insts cycles that
-        // it takes to hit a node that ends with Z. all of the nodes will
-        // line up at the lowest common multiple of all the frequencies.
-        // the data has to be constructed in a way that this will work
-        Some(
-            map.keys()
-                .filter(|x| x.ends_with("A"))
-                .map(|key| &map[key])
-                .sorted_by(|n1, n2| n1.name.cmp(&n2.name))
-                .map(|node| node.frequency(&map, &insts) as i64)
-                .product::<i64>()
-                * insts.len() as i64,
-        )
+        let ghosts = map
+            .keys()
+            .filter(|x| x.ends_with('A'))
+            .sorted()
+            .map(|key| map[key].ghost(&map, &insts))
+            .collect_vec();
+        match first_alignment(&ghosts) {
+            Some(steps) => steps.try_into().ok(),
+            None => {
+                eprintln!("The ghosts are never all on Z nodes at the same time");
+                None
+            }
+        }
     }
 }
@@ -1,8 +1,9 @@
 use std::str::FromStr;
 
 use itertools::Itertools;
+use num::{BigInt, One, ToPrimitive, Zero};
 
-use crate::Solution;
+use crate::{Options, Solution};
 
 pub struct Day9;
 
@@ -10,7 +11,7 @@ pub struct Day9;
 struct Input(Vec<Sequence>);
 
 #[derive(Debug)]
-struct Sequence(Vec<i64>);
+pub(crate) struct Sequence(pub(crate) Vec<i64>);
 
 impl FromStr for Input {
     type Err = &'static str;
@@ -35,40 +36,79 @@ impl FromStr for Sequence {
 }
 
 impl Sequence {
-    fn diff_seq(&self) -> Sequence {
-        let Sequence(seq) = self;
-        Sequence(seq.iter().tuple_windows().map(|(x, y)| y - x).collect_vec())
+    /// The first value of every layer of differences, down to the first layer that is
+    /// constant: `[a(0), Δa(0), Δ²a(0), ...]`. These are the coefficients of the sequence
+    /// in Newton's forward difference form, `a(n) = Σ C(n, k) Δᵏa(0)`.
+    fn newton_coefficients(&self) -> Vec<BigInt> {
+        let mut layer = self.0.iter().map(|&x| BigInt::from(x)).collect_vec();
+        let mut coefficients = vec![];
+        while let Some(first) = layer.first() {
+            coefficients.push(first.clone());
+            if layer.iter().all_equal() {
+                break;
+            }
+            layer = layer
+                .iter()
+                .tuple_windows()
+                .map(|(x, y)| y - x)
+                .collect_vec();
+        }
+        coefficients
     }
 
-    fn extrapolate(&self) -> i64 {
-        let Sequence(seq) = self;
-        if seq.iter().all_equal() {
-            seq[0]
-        } else {
-            let next_layer = self.diff_seq().extrapolate();
-            seq[seq.len() - 1] + next_layer
-        }
+    /// The degree of the polynomial that generates the sequence. If this is one less than
+    /// the length of the sequence, there weren't enough values to be sure of it.
+    pub(crate) fn degree(&self) -> usize {
+        self.newton_coefficients().len().saturating_sub(1)
     }
 
-    fn extrapolate_backwards(&self) -> i64 {
-        let Sequence(seq) = self;
-        if seq.iter().all_equal() {
-            seq[0]
-        } else {
-            let next_layer = self.diff_seq().extrapolate_backwards();
-            seq[0] - next_layer
+    /// The value at index `n` of the sequence, where the given values are at `0..len`.
+    /// This works in both directions, since `C(n, k) = n (n - 1) ... (n - k + 1) / k!`
+    /// is just as good for negative `n`.
+    pub(crate) fn value_at(&self, n: i64) -> BigInt {
+        let mut binomial = BigInt::one();
+        let mut value = BigInt::zero();
+        for (k, coefficient) in self.newton_coefficients().iter().enumerate() {
+            if k > 0 {
+                // C(n, k) = C(n, k - 1) * (n - k + 1) / k, which always divides exactly
+                binomial = binomial * (n - k as i64 + 1) / k;
+            }
+            value += &binomial * coefficient;
         }
+        value
+    }
+
+    /// The next value after the end of the sequence, if it fits in an `i64`
+    pub(crate) fn extrapolate(&self) -> Option<i64> {
+        self.value_at(self.0.len() as i64).to_i64()
+    }
+
+    /// The value before the start of the sequence, if it fits in an `i64`
+    fn extrapolate_backwards(&self) -> Option<i64> {
+        self.value_at(-1).to_i64()
     }
 }
 
 impl Solution for Day9 {
     fn part1(input: &str) -> Option<i64> {
+        Self::part1_with(input, &Options::default())
+    }
+
+    /// Pass `-o at=N` to print the degree of every sequence, and its value at index `N`
+    fn part1_with(input: &str, opts: &Options) -> Option<i64> {
         let Input(seq) = input.parse().unwrap();
-        Some(seq.iter().map(|s| s.extrapolate()).sum())
+        if let Some(n) = opts.get("at") {
+            for s in &seq {
+                println!("degree {}, value at {n}: {}", s.degree(), s.value_at(n));
+            }
+        }
+        seq.iter()
+            .try_fold(0i64, |sum, s| sum.checked_add(s.extrapolate()?))
     }
 
     fn part2(input: &str) -> Option<i64> {
         let Input(seq) = input.parse().unwrap();
-        Some(seq.iter().map(|s| s.extrapolate_backwards()).sum())
+        seq.iter()
+            .try_fold(0i64, |sum, s| sum.checked_add(s.extrapolate_backwards()?))
     }
 }
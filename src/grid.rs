@@ -0,0 +1,415 @@
+use std::{
+    fmt::Display,
+    ops::{Index, IndexMut, Neg},
+    str::FromStr,
+};
+
+use itertools::Itertools;
+
+/// Conversion from a single character of a char map into a grid cell
+pub trait FromChar: Sized {
+    fn from_char(c: char) -> Result<Self, &'static str>;
+}
+
+impl FromChar for char {
+    fn from_char(c: char) -> Result<Self, &'static str> {
+        Ok(c)
+    }
+}
+
+/// A single digit, for maps of heights or costs
+impl FromChar for u32 {
+    fn from_char(c: char) -> Result<Self, &'static str> {
+        c.to_digit(10).ok_or("Not a digit")
+    }
+}
+
+/// A position in a grid, as `(x, y)`, with `(0, 0)` in the top left corner
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Pos(pub usize, pub usize);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+/// A rectangular 2D grid, for all the days that come with a char map as input.
+/// The cells are stored row by row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+const OFFSETS4: [(i64, i64); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+const OFFSETS8: [(i64, i64); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+];
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    pub fn offset(&self) -> (i64, i64) {
+        match self {
+            Direction::North => (0, -1),
+            Direction::East => (1, 0),
+            Direction::South => (0, 1),
+            Direction::West => (-1, 0),
+        }
+    }
+
+    pub fn turn_right(&self) -> Direction {
+        match self {
+            Direction::North => Direction::East,
+            Direction::East => Direction::South,
+            Direction::South => Direction::West,
+            Direction::West => Direction::North,
+        }
+    }
+
+    pub fn turn_left(&self) -> Direction {
+        -self.turn_right()
+    }
+}
+
+impl Neg for Direction {
+    type Output = Direction;
+
+    fn neg(self) -> Self::Output {
+        match self {
+            Direction::North => Direction::South,
+            Direction::East => Direction::West,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+        }
+    }
+}
+
+// == Parsing ==
+
+impl<T: FromChar> FromStr for Grid<T> {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Grid::from_rows(
+            s.lines()
+                .map(|line| line.chars().map(T::from_char).collect::<Result<_, _>>())
+                .collect::<Result<_, _>>()?,
+        )
+    }
+}
+
+impl<T: Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{cell}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+// == Access ==
+
+impl<T> Grid<T> {
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Self, &'static str> {
+        let height = rows.len();
+        let width = rows.first().map(|row| row.len()).unwrap_or(0);
+        if rows.iter().any(|row| row.len() != width) {
+            return Err("Rows have different lengths");
+        }
+        Ok(Grid {
+            cells: rows.into_iter().flatten().collect(),
+            width,
+            height,
+        })
+    }
+
+    pub fn new(width: usize, height: usize, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        Grid {
+            cells: vec![fill; width * height],
+            width,
+            height,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, Pos(x, y): Pos) -> bool {
+        x < self.width && y < self.height
+    }
+
+    #[allow(dead_code)]
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        self.contains(pos).then(|| &self[pos])
+    }
+
+    #[allow(dead_code)]
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        if self.contains(pos) {
+            Some(&mut self[pos])
+        } else {
+            None
+        }
+    }
+
+    /// Every position in the grid, row by row
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let width = self.width;
+        (0..self.height)
+            .cartesian_product(0..width)
+            .map(|(y, x)| Pos(x, y))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    /// The position of the first cell, row by row, that matches `pred`
+    pub fn position(&self, pred: impl Fn(&T) -> bool) -> Option<Pos> {
+        self.iter().find(|(_, cell)| pred(cell)).map(|(pos, _)| pos)
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // `chunks` panics on a chunk size of 0
+        self.cells.chunks(self.width.max(1))
+    }
+
+    #[allow(dead_code)]
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        self.cells.iter().skip(x).step_by(self.width.max(1))
+    }
+
+    #[allow(dead_code)]
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|x| self.column(x))
+    }
+
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Grid<U> {
+        Grid {
+            cells: self.cells.iter().map(f).collect(),
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
+
+// == Neighbors ==
+
+impl<T> Grid<T> {
+    /// Move `pos` by `(dx, dy)`, if that stays inside the grid
+    pub fn offset(&self, Pos(x, y): Pos, (dx, dy): (i64, i64)) -> Option<Pos> {
+        let x = x.checked_add_signed(dx as isize)?;
+        let y = y.checked_add_signed(dy as isize)?;
+        self.contains(Pos(x, y)).then_some(Pos(x, y))
+    }
+
+    /// Move `pos` by `(dx, dy)`, wrapping around the edges as if the grid repeated forever.
+    /// Panics if the grid is empty, as there is nowhere to wrap around to.
+    pub fn wrapping_offset(&self, Pos(x, y): Pos, (dx, dy): (i64, i64)) -> Pos {
+        assert!(
+            self.width > 0 && self.height > 0,
+            "Can't wrap around an empty grid"
+        );
+        Pos(
+            (x as i64 + dx).rem_euclid(self.width as i64) as usize,
+            (y as i64 + dy).rem_euclid(self.height as i64) as usize,
+        )
+    }
+
+    /// The neighbor of `pos` in `dir`, if there is one
+    pub fn step(&self, pos: Pos, dir: Direction) -> Option<Pos> {
+        self.offset(pos, dir.offset())
+    }
+
+    /// The (up to) 4 orthogonal neighbors of `pos` that are inside the grid
+    pub fn neighbors4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        OFFSETS4
+            .into_iter()
+            .filter_map(move |off| self.offset(pos, off))
+    }
+
+    /// The (up to) 8 orthogonal and diagonal neighbors of `pos` that are inside the grid
+    pub fn neighbors8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        OFFSETS8
+            .into_iter()
+            .filter_map(move |off| self.offset(pos, off))
+    }
+
+    /// The 4 orthogonal neighbors of `pos`, wrapping around the edges
+    #[allow(dead_code)]
+    pub fn wrapping_neighbors4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        OFFSETS4
+            .into_iter()
+            .map(move |off| self.wrapping_offset(pos, off))
+    }
+
+    /// The 8 orthogonal and diagonal neighbors of `pos`, wrapping around the edges
+    #[allow(dead_code)]
+    pub fn wrapping_neighbors8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        OFFSETS8
+            .into_iter()
+            .map(move |off| self.wrapping_offset(pos, off))
+    }
+}
+
+// == Transformations ==
+
+// nothing needs these until the mirror and tilting puzzles (days 13 and 14) are solved
+#[allow(dead_code)]
+impl<T: Clone> Grid<T> {
+    /// Flip the grid over its main diagonal, so rows become columns
+    pub fn transpose(&self) -> Grid<T> {
+        Grid {
+            cells: self.columns().flatten().cloned().collect(),
+            width: self.height,
+            height: self.width,
+        }
+    }
+
+    /// Rotate the grid a quarter turn clockwise
+    pub fn rotate_clockwise(&self) -> Grid<T> {
+        Grid {
+            cells: self
+                .columns()
+                .flat_map(|col| col.collect_vec().into_iter().rev())
+                .cloned()
+                .collect(),
+            width: self.height,
+            height: self.width,
+        }
+    }
+
+    /// Rotate the grid a quarter turn counterclockwise
+    pub fn rotate_counterclockwise(&self) -> Grid<T> {
+        Grid {
+            cells: (0..self.width)
+                .rev()
+                .flat_map(|x| self.column(x))
+                .cloned()
+                .collect(),
+            width: self.height,
+            height: self.width,
+        }
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, Pos(x, y): Pos) -> &Self::Output {
+        assert!(
+            x < self.width && y < self.height,
+            "{:?} is outside of the grid",
+            Pos(x, y)
+        );
+        &self.cells[y * self.width + x]
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, Pos(x, y): Pos) -> &mut Self::Output {
+        assert!(
+            x < self.width && y < self.height,
+            "{:?} is outside of the grid",
+            Pos(x, y)
+        );
+        &mut self.cells[y * self.width + x]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 3 wide and 2 high, so that mixing up the width and height shows
+    fn grid() -> Grid<char> {
+        "abc\ndef".parse().unwrap()
+    }
+
+    #[test]
+    fn transpose() {
+        let transposed = grid().transpose();
+        assert_eq!((transposed.width(), transposed.height()), (2, 3));
+        assert_eq!(transposed.to_string(), "ad\nbe\ncf\n");
+        assert_eq!(transposed.transpose(), grid());
+    }
+
+    #[test]
+    fn rotations() {
+        let clockwise = grid().rotate_clockwise();
+        assert_eq!((clockwise.width(), clockwise.height()), (2, 3));
+        assert_eq!(clockwise.to_string(), "da\neb\nfc\n");
+
+        let counterclockwise = grid().rotate_counterclockwise();
+        assert_eq!(counterclockwise.to_string(), "cf\nbe\nad\n");
+
+        assert_eq!(clockwise.rotate_counterclockwise(), grid());
+        let upside_down = clockwise.rotate_clockwise();
+        assert_eq!(upside_down.to_string(), "fed\ncba\n");
+    }
+
+    #[test]
+    fn neighbors_at_corner() {
+        let grid = grid();
+        assert_eq!(
+            grid.neighbors4(Pos(0, 0)).collect_vec(),
+            vec![Pos(1, 0), Pos(0, 1)]
+        );
+        assert_eq!(
+            grid.wrapping_neighbors4(Pos(0, 0)).collect_vec(),
+            vec![Pos(0, 1), Pos(1, 0), Pos(0, 1), Pos(2, 0)]
+        );
+        assert_eq!(
+            grid.wrapping_neighbors8(Pos(2, 1)).collect_vec(),
+            vec![
+                Pos(1, 0),
+                Pos(2, 0),
+                Pos(0, 0),
+                Pos(0, 1),
+                Pos(0, 0),
+                Pos(2, 0),
+                Pos(1, 0),
+                Pos(1, 1),
+            ]
+        );
+        assert_eq!(grid.wrapping_offset(Pos(0, 0), (-4, 5)), Pos(2, 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't wrap around an empty grid")]
+    fn wrapping_on_empty_grid() {
+        Grid::<char>::new(0, 0, '.').wrapping_offset(Pos(0, 0), (1, 0));
+    }
+}
@@ -1,5 +1,4 @@
 mod day1;
-#[allow(unused)]
 mod day10;
 #[allow(unused)]
 mod day11;
@@ -45,8 +44,12 @@ mod day7;
 mod day8;
 #[allow(unused)]
 mod day9;
+mod grid;
+#[allow(unused)]
+mod math;
+mod search;
 
-use std::{fs::File, io::Read, path::PathBuf, time::Instant};
+use std::{collections::HashMap, fs::File, io::Read, path::PathBuf, str::FromStr, time::Instant};
 
 use argh::FromArgs;
 use day1::Day1;
@@ -85,33 +88,78 @@ struct Cmdline {
     /// use short data for testing
     #[argh(switch)]
     short: bool,
+
+    /// day specific options, given as `key=value` or just `key` for switches
+    #[argh(option, short = 'o')]
+    opt: Vec<String>,
+}
+
+/// The day specific options passed with `-o`
+#[derive(Debug, Default)]
+struct Options(HashMap<String, String>);
+
+impl Options {
+    fn new(opts: &[String]) -> Self {
+        Options(
+            opts.iter()
+                .map(|opt| match opt.split_once('=') {
+                    Some((key, value)) => (key.to_string(), value.to_string()),
+                    None => (opt.to_string(), String::new()),
+                })
+                .collect(),
+        )
+    }
+
+    /// Is the switch `key` set?
+    fn flag(&self, key: &str) -> bool {
+        self.0.contains_key(key)
+    }
+
+    /// Parse the value given for `key`, if there is one
+    fn get<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.0.get(key).map(|value| {
+            value
+                .parse()
+                .unwrap_or_else(|_| panic!("Invalid value for option {key}: {value:?}"))
+        })
+    }
 }
 
 trait Solution {
     fn part1(input: &str) -> Option<i64>;
     fn part2(input: &str) -> Option<i64>;
-    fn run(input: &str, short: bool) {
+
+    /// Versions of `part1` and `part2` that get to look at the `-o` options.
+    /// Days that don't have any options just use the plain versions.
+    fn part1_with(input: &str, _opts: &Options) -> Option<i64> {
+        Self::part1(input)
+    }
+    fn part2_with(input: &str, _opts: &Options) -> Option<i64> {
+        Self::part2(input)
+    }
+
+    fn run(input: &str, short: bool, opts: &Options) {
         let (part1_sol, part1_time, part2_sol, part2_time) = if short {
             let (part1_input, part2_input) =
                 input.split_at(input.find("---").expect("Couldn't find divider"));
             let part2_input = &part2_input[4..];
 
             let now = Instant::now();
-            let part1_sol = Self::part1(part1_input);
+            let part1_sol = Self::part1_with(part1_input, opts);
             let part1_time = now.elapsed();
 
             let now = Instant::now();
-            let part2_sol = Self::part2(part2_input);
+            let part2_sol = Self::part2_with(part2_input, opts);
             let part2_time = now.elapsed();
 
             (part1_sol, part1_time, part2_sol, part2_time)
         } else {
             let now = Instant::now();
-            let part1_sol = Self::part1(input);
+            let part1_sol = Self::part1_with(input, opts);
             let part1_time = now.elapsed();
 
             let now = Instant::now();
-            let part2_sol = Self::part2(input);
+            let part2_sol = Self::part2_with(input, opts);
             let part2_time = now.elapsed();
 
             (part1_sol, part1_time, part2_sol, part2_time)
@@ -125,6 +173,7 @@ trait Solution {
 
 fn main() {
     let args: Cmdline = argh::from_env();
+    let opts = Options::new(&args.opt);
 
     // construct file path from command line arguments
     let data_path = if args.short {
@@ -151,31 +200,31 @@ fn main() {
     }
 
     match args.day.as_str() {
-        "day1" => Day1::run(&input, args.short),
-        "day2" => Day2::run(&input, args.short),
-        "day3" => Day3::run(&input, args.short),
-        "day4" => Day4::run(&input, args.short),
-        "day5" => Day5::run(&input, args.short),
-        "day6" => Day6::run(&input, args.short),
-        "day7" => Day7::run(&input, args.short),
-        "day8" => Day8::run(&input, args.short),
-        "day9" => Day9::run(&input, args.short),
-        "day10" => Day10::run(&input, args.short),
-        "day11" => Day11::run(&input, args.short),
-        "day12" => Day12::run(&input, args.short),
-        "day13" => Day13::run(&input, args.short),
-        "day14" => Day14::run(&input, args.short),
-        "day15" => Day15::run(&input, args.short),
-        "day16" => Day16::run(&input, args.short),
-        "day17" => Day17::run(&input, args.short),
-        "day18" => Day18::run(&input, args.short),
-        "day19" => Day19::run(&input, args.short),
-        "day20" => Day20::run(&input, args.short),
-        "day21" => Day21::run(&input, args.short),
-        "day22" => Day22::run(&input, args.short),
-        "day23" => Day23::run(&input, args.short),
-        "day24" => Day24::run(&input, args.short),
-        "day25" => Day25::run(&input, args.short),
+        "day1" => Day1::run(&input, args.short, &opts),
+        "day2" => Day2::run(&input, args.short, &opts),
+        "day3" => Day3::run(&input, args.short, &opts),
+        "day4" => Day4::run(&input, args.short, &opts),
+        "day5" => Day5::run(&input, args.short, &opts),
+        "day6" => Day6::run(&input, args.short, &opts),
+        "day7" => Day7::run(&input, args.short, &opts),
+        "day8" => Day8::run(&input, args.short, &opts),
+        "day9" => Day9::run(&input, args.short, &opts),
+        "day10" => Day10::run(&input, args.short, &opts),
+        "day11" => Day11::run(&input, args.short, &opts),
+        "day12" => Day12::run(&input, args.short, &opts),
+        "day13" => Day13::run(&input, args.short, &opts),
+        "day14" => Day14::run(&input, args.short, &opts),
+        "day15" => Day15::run(&input, args.short, &opts),
+        "day16" => Day16::run(&input, args.short, &opts),
+        "day17" => Day17::run(&input, args.short, &opts),
+        "day18" => Day18::run(&input, args.short, &opts),
+        "day19" => Day19::run(&input, args.short, &opts),
+        "day20" => Day20::run(&input, args.short, &opts),
+        "day21" => Day21::run(&input, args.short, &opts),
+        "day22" => Day22::run(&input, args.short, &opts),
+        "day23" => Day23::run(&input, args.short, &opts),
+        "day24" => Day24::run(&input, args.short, &opts),
+        "day25" => Day25::run(&input, args.short, &opts),
         _ => panic!("Unknown day"),
     }
 }
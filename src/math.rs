@@ -0,0 +1,45 @@
+pub fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+pub fn lcm(a: u64, b: u64) -> u64 {
+    a / gcd(a, b) * b
+}
+
+/// Extended Euclid: returns `(g, x, y)` with `g = gcd(a, b)` and `a * x + b * y = g`
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a.abs(), a.signum(), 0)
+    } else {
+        let (g, x, y) = extended_gcd(b, a.rem_euclid(b));
+        (g, y, x - a.div_euclid(b) * y)
+    }
+}
+
+/// Generalized chinese remainder theorem. Finds the `x` that solves every
+/// `x ≡ residue (mod modulus)` at once, where the moduli don't have to be coprime.
+/// Returns `(x, m)` with `0 <= x < m`, so that the solutions are exactly `x + k * m`,
+/// or `None` if the congruences contradict each other.
+///
+/// The combined modulus has to fit in an `i128`, and the intermediate products are
+/// at most the square of a single modulus, so moduli up to about 10^19 are fine.
+pub fn crt(congruences: impl IntoIterator<Item = (i128, i128)>) -> Option<(i128, i128)> {
+    congruences
+        .into_iter()
+        .try_fold((0, 1), |(r1, m1), (r2, m2)| {
+            // x = r1 + m1 * k, so we need m1 * k ≡ r2 - r1 (mod m2)
+            let (g, p, _) = extended_gcd(m1, m2);
+            let diff = r2 - r1;
+            if diff % g != 0 {
+                return None;
+            }
+            let step = m2 / g;
+            let k = (diff / g).rem_euclid(step) * p.rem_euclid(step) % step;
+            let m = m1 * step;
+            Some(((r1 + m1 * k).rem_euclid(m), m))
+        })
+}
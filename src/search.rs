@@ -0,0 +1,316 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, VecDeque},
+    hash::Hash,
+    ops::Add,
+};
+
+/// The result of a graph search: the cost to reach every state that the search
+/// got to, and the state that it was reached from. If the search was looking for
+/// a goal and found one, that is in `goal`.
+#[derive(Debug)]
+pub struct Search<S, C> {
+    pub dist: HashMap<S, C>,
+    pub prev: HashMap<S, S>,
+    pub goal: Option<S>,
+}
+
+impl<S: Clone + Eq + Hash, C> Search<S, C> {
+    fn new() -> Self {
+        Search {
+            dist: HashMap::new(),
+            prev: HashMap::new(),
+            goal: None,
+        }
+    }
+
+    /// Walk the predecessors back from `target` to one of the starting states.
+    /// The path includes both ends.
+    pub fn path_to(&self, target: &S) -> Option<Vec<S>> {
+        if !self.dist.contains_key(target) {
+            return None;
+        }
+        let mut path = vec![target.clone()];
+        while let Some(prev) = self.prev.get(path.last().unwrap()) {
+            path.push(prev.clone());
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// The path to the goal, if one was found
+    pub fn path(&self) -> Option<Vec<S>> {
+        self.path_to(self.goal.as_ref()?)
+    }
+
+    /// The cost of reaching the goal, if one was found
+    pub fn cost(&self) -> Option<&C> {
+        self.dist.get(self.goal.as_ref()?)
+    }
+}
+
+/// Breadth first search from all of the `starts`, stopping early if we reach a state
+/// for which `is_goal` holds. Pass `|_| false` to explore everything reachable.
+pub fn bfs<S, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut successors: impl FnMut(&S) -> I,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Search<S, usize>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    let mut search = Search::new();
+    let mut queue = VecDeque::new();
+    for start in starts {
+        if !search.dist.contains_key(&start) {
+            search.dist.insert(start.clone(), 0);
+            queue.push_back(start);
+        }
+    }
+
+    while let Some(state) = queue.pop_front() {
+        if is_goal(&state) {
+            search.goal = Some(state);
+            break;
+        }
+        let d = search.dist[&state];
+        for next in successors(&state) {
+            if !search.dist.contains_key(&next) {
+                search.dist.insert(next.clone(), d + 1);
+                search.prev.insert(next.clone(), state.clone());
+                queue.push_back(next);
+            }
+        }
+    }
+    search
+}
+
+/// Depth first search from `start`. Successors are explored in the order they are
+/// returned, so the first successor's whole subtree comes before the second one.
+/// `dist` holds the depth of every state in the DFS tree.
+#[allow(dead_code)]
+pub fn dfs<S, I>(
+    start: S,
+    mut successors: impl FnMut(&S) -> I,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Search<S, usize>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    let mut search = Search::new();
+    let mut stack = vec![(start, None, 0)];
+
+    while let Some((state, prev, depth)) = stack.pop() {
+        if search.dist.contains_key(&state) {
+            continue;
+        }
+        search.dist.insert(state.clone(), depth);
+        if let Some(prev) = prev {
+            search.prev.insert(state.clone(), prev);
+        }
+        if is_goal(&state) {
+            search.goal = Some(state);
+            break;
+        }
+        let next = successors(&state)
+            .into_iter()
+            .filter(|next| !search.dist.contains_key(next))
+            .collect::<Vec<_>>();
+        stack.extend(
+            next.into_iter()
+                .rev()
+                .map(|next| (next, Some(state.clone()), depth + 1)),
+        );
+    }
+    search
+}
+
+/// Dijkstra's algorithm from `start`, where `successors` gives the next states along with
+/// the cost of moving there. Stops once the cheapest state left is a goal.
+pub fn dijkstra<S, C, I>(
+    start: S,
+    successors: impl FnMut(&S) -> I,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Search<S, C>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (S, C)>,
+{
+    astar(start, successors, |_| C::default(), is_goal)
+}
+
+/// A* search from `start`. The `heuristic` has to never overestimate the remaining cost
+/// to a goal, otherwise the path found might not be the cheapest one.
+pub fn astar<S, C, I>(
+    start: S,
+    mut successors: impl FnMut(&S) -> I,
+    mut heuristic: impl FnMut(&S) -> C,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> Search<S, C>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (S, C)>,
+{
+    let mut search = Search::new();
+    // the heap only holds indices into `states`, so that states don't have to be `Ord`
+    let mut states = vec![start.clone()];
+    let mut queue = BinaryHeap::from([Reverse((heuristic(&start), C::default(), 0))]);
+    search.dist.insert(start, C::default());
+
+    while let Some(Reverse((_, cost, idx))) = queue.pop() {
+        let state = states[idx].clone();
+        if cost > search.dist[&state] {
+            continue;
+        }
+        if is_goal(&state) {
+            search.goal = Some(state);
+            break;
+        }
+        for (next, step) in successors(&state) {
+            let next_cost = cost + step;
+            if search.dist.get(&next).is_none_or(|d| next_cost < *d) {
+                search.dist.insert(next.clone(), next_cost);
+                search.prev.insert(next.clone(), state.clone());
+                queue.push(Reverse((
+                    next_cost + heuristic(&next),
+                    next_cost,
+                    states.len(),
+                )));
+                states.push(next);
+            }
+        }
+    }
+    search
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::{Grid, Pos};
+
+    /// The only way from the top left to the top right goes around the bottom
+    const MAZE: &str = "..#.\n.##.\n....";
+
+    const AROUND: [Pos; 8] = [
+        Pos(0, 0),
+        Pos(0, 1),
+        Pos(0, 2),
+        Pos(1, 2),
+        Pos(2, 2),
+        Pos(3, 2),
+        Pos(3, 1),
+        Pos(3, 0),
+    ];
+
+    /// The cost of entering each block. Going down the left and along the bottom
+    /// costs 4, going along the top and down the right costs 6.
+    const COSTS: &str = "131\n191\n111";
+
+    fn open_neighbors<'a>(grid: &'a Grid<char>, pos: &Pos) -> impl Iterator<Item = Pos> + 'a {
+        grid.neighbors4(*pos).filter(|next| grid[*next] != '#')
+    }
+
+    fn weighted_neighbors<'a>(
+        grid: &'a Grid<u32>,
+        pos: &Pos,
+    ) -> impl Iterator<Item = (Pos, u32)> + 'a {
+        grid.neighbors4(*pos).map(|next| (next, grid[next]))
+    }
+
+    #[test]
+    fn bfs_finds_shortest_path() {
+        let grid: Grid<char> = MAZE.parse().unwrap();
+        let search = bfs(
+            [Pos(0, 0)],
+            |pos| open_neighbors(&grid, pos),
+            |pos| *pos == Pos(3, 0),
+        );
+        assert_eq!(search.goal, Some(Pos(3, 0)));
+        assert_eq!(search.cost(), Some(&7));
+        assert_eq!(search.prev[&Pos(0, 1)], Pos(0, 0));
+        assert_eq!(search.path(), Some(AROUND.to_vec()));
+    }
+
+    #[test]
+    fn bfs_without_goal_explores_everything() {
+        let grid: Grid<char> = MAZE.parse().unwrap();
+        let search = bfs([Pos(0, 0)], |pos| open_neighbors(&grid, pos), |_| false);
+        assert_eq!(search.goal, None);
+        assert_eq!(search.path(), None);
+        assert_eq!(search.dist.len(), 9);
+        assert_eq!(search.dist[&Pos(1, 0)], 1);
+        assert_eq!(search.dist[&Pos(3, 1)], 6);
+        assert_eq!(search.path_to(&Pos(2, 0)), None);
+    }
+
+    #[test]
+    fn dfs_follows_first_successor_first() {
+        let grid: Grid<char> = MAZE.parse().unwrap();
+        let search = dfs(
+            Pos(0, 0),
+            |pos| open_neighbors(&grid, pos),
+            |pos| *pos == Pos(3, 0),
+        );
+        // east is tried before south, so the dead end at (1, 0) is visited first
+        assert_eq!(search.dist[&Pos(1, 0)], 1);
+        assert_eq!(search.prev[&Pos(1, 0)], Pos(0, 0));
+        assert_eq!(search.cost(), Some(&7));
+        assert_eq!(search.path(), Some(AROUND.to_vec()));
+    }
+
+    #[test]
+    fn dijkstra_distances_and_path() {
+        let grid: Grid<u32> = COSTS.parse().unwrap();
+        let all = dijkstra(Pos(0, 0), |pos| weighted_neighbors(&grid, pos), |_| false);
+        assert_eq!(all.dist[&Pos(0, 0)], 0);
+        assert_eq!(all.dist[&Pos(2, 0)], 4);
+        assert_eq!(all.dist[&Pos(2, 1)], 5);
+        assert_eq!(all.dist[&Pos(1, 1)], 10);
+        assert_eq!(all.prev[&Pos(1, 1)], Pos(0, 1));
+
+        let search = dijkstra(
+            Pos(0, 0),
+            |pos| weighted_neighbors(&grid, pos),
+            |pos| *pos == Pos(2, 2),
+        );
+        assert_eq!(search.cost(), Some(&4));
+        assert_eq!(
+            search.path(),
+            Some(vec![Pos(0, 0), Pos(0, 1), Pos(0, 2), Pos(1, 2), Pos(2, 2)])
+        );
+    }
+
+    #[test]
+    fn astar_agrees_with_dijkstra() {
+        let grid: Grid<u32> = COSTS.parse().unwrap();
+        let goal = Pos(2, 2);
+        let search = astar(
+            Pos(0, 0),
+            |pos| weighted_neighbors(&grid, pos),
+            |Pos(x, y)| ((goal.0 - x) + (goal.1 - y)) as u32,
+            |pos| *pos == goal,
+        );
+        assert_eq!(search.cost(), Some(&4));
+        assert_eq!(
+            search.path(),
+            Some(vec![Pos(0, 0), Pos(0, 1), Pos(0, 2), Pos(1, 2), Pos(2, 2)])
+        );
+    }
+
+    #[test]
+    fn unreachable_goal() {
+        let grid: Grid<char> = "..#.\n..#.".parse().unwrap();
+        let search = bfs(
+            [Pos(0, 0)],
+            |pos| open_neighbors(&grid, pos),
+            |pos| *pos == Pos(3, 0),
+        );
+        assert_eq!(search.goal, None);
+        assert_eq!(search.cost(), None);
+        assert_eq!(search.dist.len(), 4);
+    }
+}